@@ -0,0 +1,160 @@
+use crate::{AccentsColor, Mode};
+use serde::Deserialize;
+
+/// Defaults of the command line options, the options given on the command line win
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub preset: Option<String>,
+    pub mode: Option<Mode>,
+    pub accent: Option<AccentsColor>,
+    pub store: Option<String>,
+    pub gtk3_path: Option<String>,
+    pub gtk4_path: Option<String>,
+    pub theme_dir: Option<String>,
+    /// Read-only stores loaded before the user store, the last one wins
+    pub store_layers: Vec<String>,
+}
+
+impl Config {
+    /// Reads the config file, a missing file is an empty config
+    pub fn load(path: &str) -> Config {
+        match std::fs::read_to_string(path) {
+            Ok(content) => Config::parse(&content)
+                .unwrap_or_else(|e| panic!("Invalid config file {}: {}", path, e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Config::default(),
+            Err(e) => panic!("Unable to read the config file {}: {}", path, e),
+        }
+    }
+
+    /// Parses a config, `~` is expanded in the paths
+    pub fn parse(content: &str) -> Result<Config, toml::de::Error> {
+        let config: Config = toml::from_str(content)?;
+        let expand = |path: Option<String>| path.map(|path| shellexpand::tilde(&path).to_string());
+        Ok(Config {
+            preset: expand(config.preset),
+            store: expand(config.store),
+            gtk3_path: expand(config.gtk3_path),
+            gtk4_path: expand(config.gtk4_path),
+            theme_dir: expand(config.theme_dir),
+            store_layers: config
+                .store_layers
+                .iter()
+                .map(|dir| shellexpand::tilde(dir).to_string())
+                .collect(),
+            ..config
+        })
+    }
+}
+
+/// Returns `$XDG_CONFIG_HOME/gradience`, `~/.config/gradience` by default
+pub fn config_dir() -> String {
+    format!("{}/gradience", xdg_dir("XDG_CONFIG_HOME", "~/.config"))
+}
+
+/// Returns `$XDG_DATA_HOME/gradience`, `~/.local/share/gradience` by default
+pub fn data_dir() -> String {
+    format!("{}/gradience", xdg_dir("XDG_DATA_HOME", "~/.local/share"))
+}
+
+/// Returns the `gradience/presets` directories of `$XDG_DATA_DIRS`, the most important
+/// one first like in the variable
+pub fn system_stores() -> Vec<String> {
+    system_stores_in(std::env::var("XDG_DATA_DIRS").ok())
+}
+
+fn system_stores_in(dirs: Option<String>) -> Vec<String> {
+    let dirs = match dirs {
+        Some(dirs) if !dirs.is_empty() => dirs,
+        _ => "/usr/local/share:/usr/share".to_string(),
+    };
+    dirs.split(':')
+        .filter(|dir| std::path::Path::new(dir).is_absolute())
+        .map(|dir| format!("{}/gradience/presets", dir.trim_end_matches('/')))
+        .collect()
+}
+
+fn xdg_dir(variable: &str, default: &str) -> String {
+    xdg_dir_or(std::env::var(variable).ok(), default)
+}
+
+/// The spec requires XDG directories to be absolute, others are ignored
+fn xdg_dir_or(dir: Option<String>, default: &str) -> String {
+    match dir {
+        Some(dir) if std::path::Path::new(&dir).is_absolute() => dir,
+        _ => shellexpand::tilde(default).to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn home() -> String {
+        shellexpand::tilde("~").to_string()
+    }
+
+    #[test]
+    fn config_is_parsed_with_expanded_paths() {
+        let config = Config::parse(
+            r#"
+            preset = "rose-pine"
+            mode = "dark"
+            accent = "teal"
+            store = "~/presets"
+            store_layers = ["~/shared", "/srv/presets"]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.preset.as_deref(), Some("rose-pine"));
+        assert_eq!(config.mode, Some(Mode::Dark));
+        assert_eq!(config.accent, Some(AccentsColor::Teal));
+        assert_eq!(config.store, Some(format!("{}/presets", home())));
+        assert_eq!(
+            config.store_layers,
+            vec![format!("{}/shared", home()), "/srv/presets".to_string()]
+        );
+        assert_eq!(config.theme_dir, None);
+    }
+
+    #[test]
+    fn invalid_configs_are_rejected() {
+        assert!(Config::parse("theme = \"nord\"").is_err());
+        assert!(Config::parse("mode = \"dim\"").is_err());
+        assert!(Config::parse("store_layers = \"/srv\"").is_err());
+        assert!(Config::load("/nonexistent/config.toml").preset.is_none());
+    }
+
+    #[test]
+    fn relative_xdg_dirs_are_ignored() {
+        let default = format!("{}/.local/share", home());
+        assert_eq!(
+            xdg_dir_or(Some("/data".to_string()), "~/.local/share"),
+            "/data"
+        );
+        assert_eq!(
+            xdg_dir_or(Some("data".to_string()), "~/.local/share"),
+            default
+        );
+        assert_eq!(xdg_dir_or(None, "~/.local/share"), default);
+    }
+
+    #[test]
+    fn system_stores_follow_xdg_data_dirs() {
+        assert_eq!(
+            system_stores_in(Some("/opt/share/:relative:/usr/share".to_string())),
+            vec![
+                "/opt/share/gradience/presets",
+                "/usr/share/gradience/presets"
+            ]
+        );
+        assert_eq!(
+            system_stores_in(Some(String::new())),
+            vec![
+                "/usr/local/share/gradience/presets",
+                "/usr/share/gradience/presets"
+            ]
+        );
+    }
+}
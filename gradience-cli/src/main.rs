@@ -1,21 +1,31 @@
+mod config;
+
 use clap::{Parser, Subcommand, ValueEnum};
-use gradience_lib::preset::{AccentsColor as GAccentsColor, Mode as GMode};
-use gradience_lib::preset::{ApplyBuilder, Preset};
-use gradience_lib::shell::Shell;
-use gradience_lib::store::Store;
-use include_dir::{include_dir, Dir};
+use config::{config_dir, data_dir, system_stores, Config};
+use gradience_lib::archive::{export_theme, import_theme};
+use gradience_lib::gdm::Gdm;
+use gradience_lib::preset::{AccentsColor as GAccentsColor, ApplyBuilder, Mode as GMode, Preset};
+use gradience_lib::remote::{self, Remote};
+use gradience_lib::shell::{source_layers, Shell, ShellTemplates, ThemeName};
+use gradience_lib::store::{IndexEntry, Search, Store};
+use gradience_lib::utils::{get_gnome_shell_version, ShellVersion};
+use include_dir::{include_dir, Dir, DirEntry};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::hash::Hasher;
 extern crate shellexpand;
 
 static SHELL_SOURCE: Dir<'_> = include_dir!("$CARGO_MANIFEST_DIR/../shell");
-const DEFAULT_STORE_PATH: &str = "./store";
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
 enum Mode {
     Light,
     Dark,
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
 enum AccentsColor {
     Blue,
     Teal,
@@ -53,6 +63,12 @@ impl From<AccentsColor> for GAccentsColor {
     }
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum ListFormat {
+    Table,
+    Json,
+}
+
 #[derive(Parser, Debug)]
 #[command(version, name="Gradience CLI φοῖνιξ", about="Change the look of GNOME, with ease", long_about=None)]
 struct Args {
@@ -64,18 +80,43 @@ struct Args {
     #[arg(short, long)]
     accent: Option<AccentsColor>,
 
-    /// The path to the store where presets are stored
+    /// The path to the store where presets are stored, defaults to
+    /// $XDG_DATA_HOME/gradience/presets
     #[arg(short, long)]
     store: Option<String>,
 
+    /// The config file giving defaults for the preset, mode, accent, store, GTK paths and
+    /// theme directory, defaults to $XDG_CONFIG_HOME/gradience/config.toml
+    #[arg(long)]
+    config: Option<String>,
+
+    /// Also load the presets in subdirectories of the store
+    #[arg(long)]
+    store_recursive: bool,
+
+    /// A read-only store whose presets are also available, after the ones of $XDG_DATA_DIRS
+    /// like /usr/share/gradience/presets. Can be repeated, the last one wins and the presets
+    /// of --store win over all of them
+    #[arg(long)]
+    store_layer: Vec<String>,
+
     /// The path to the shell source directory which contains templates for the shell theme
     #[arg(long)]
     shell_source: Option<String>,
 
-    /// The name of the preset to apply, it's not the filename
+    /// A directory overriding files of the shell source, like $XDG_CONFIG_HOME/gradience/shell
+    /// which is always used, e.g. 46/widgets/_panel.template. Can be repeated, the last one wins
+    #[arg(long)]
+    shell_overlay: Vec<String>,
+
+    /// The preset to apply, a preset file or the ID, filename or name of a preset of the store
     #[arg(short, long)]
     preset: Option<String>,
 
+    /// Fail when a shell template uses a variable the preset doesn't provide
+    #[arg(long)]
+    strict: bool,
+
     #[command(subcommand)]
     command: Commands,
 
@@ -103,8 +144,95 @@ enum Commands {
         #[arg(short, long)]
         gsettings: bool,
     },
+    /// Build the shell and GTK themes without applying them
+    Build {
+        /// The path to the temporary directory where the themes will be created and build
+        #[arg(long)]
+        temp_dir: Option<String>,
+        /// The path to the directory where the themes will be stored
+        #[arg(long)]
+        theme_dir: Option<String>,
+        /// Build every mode and accent combination of the preset, ignoring --mode and --accent
+        #[arg(long)]
+        all_variants: bool,
+        /// Build the variants in parallel
+        #[arg(long, requires = "all_variants")]
+        parallel: bool,
+        /// The GNOME Shell version to build for, defaults to the installed one
+        #[arg(long)]
+        shell_version: Option<String>,
+        /// Also pack each shell theme in a gnome-shell-theme.gresource inside the theme directory
+        #[arg(long)]
+        gresource: bool,
+    },
     /// Apply the theme to the GTK theme
     Gtk,
+    /// List the shell source files and the layer each one comes from
+    ShellSources {
+        /// The GNOME Shell version to list, defaults to the installed one
+        #[arg(long)]
+        shell_version: Option<String>,
+    },
+    /// Render the shell templates in strict mode and list the variables the preset doesn't provide
+    CheckTemplates {
+        /// The GNOME Shell version to check, defaults to the installed one
+        #[arg(long)]
+        shell_version: Option<String>,
+    },
+    /// Pack a built theme in a .tar.xz or .zip archive
+    ExportTheme {
+        /// The path to the built theme, e.g. ~/.themes/Name-Dark-Blue
+        path: String,
+        /// The path to the archive to create, the format is guessed from the extension
+        archive: String,
+    },
+    /// Install a theme from a .tar.xz or .zip archive
+    ImportTheme {
+        /// The path to the theme archive
+        archive: String,
+        /// The path to the directory where the theme will be installed
+        #[arg(long)]
+        theme_dir: Option<String>,
+    },
+    /// Theme the GDM login screen
+    Gdm {
+        /// The path to the shell theme resource used by GDM
+        #[arg(long)]
+        resource: Option<String>,
+        /// The GNOME Shell version to build for, defaults to the installed one
+        #[arg(long)]
+        shell_version: Option<String>,
+
+        #[command(subcommand)]
+        command: GdmCommands,
+    },
+    /// Rewrite preset files in the canonical format
+    Fmt {
+        /// The preset files to format
+        #[arg(required = true)]
+        paths: Vec<String>,
+        /// Leave out the values equal to their default
+        #[arg(long)]
+        omit_defaults: bool,
+        /// Only list the files which aren't formatted, and fail if there are some
+        #[arg(long)]
+        check: bool,
+    },
+    /// Upgrade preset files to the current version of the preset format
+    Migrate {
+        /// The preset files to upgrade
+        #[arg(required = true)]
+        paths: Vec<String>,
+        /// Only print the changes, without writing the files
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Print the JSON Schema of preset files, for editors to validate and complete them
+    Schema {
+        /// Write the schema to this file instead of printing it
+        #[arg(long)]
+        output: Option<String>,
+    },
     /// Manage the store of presets
     Store {
         #[command(subcommand)]
@@ -121,41 +249,294 @@ enum StoreCommands {
     },
     /// Remove a preset from the store
     Remove {
-        /// The name of the preset to remove
+        /// The ID, filename or name of the preset to remove
         name: String,
     },
-    /// List the online presets
+    /// List the presets of the remotes, as remote:name
     OnlineList,
-    /// List the local presets
-    LocalList,
-    /// Download a preset from the online store
+    /// List the local presets with their metadata
+    #[command(alias = "local-list")]
+    List {
+        #[arg(long, value_enum, default_value_t = ListFormat::Table)]
+        format: ListFormat,
+    },
+    /// Search the local presets, by name, author and description
+    Search {
+        /// The text to search, letters can be skipped, e.g. "rspine" finds "Rosé Pine"
+        query: Option<String>,
+        /// Only show presets supporting this mode
+        #[arg(long)]
+        supports: Option<Mode>,
+        /// Only show presets supporting this accent
+        #[arg(long)]
+        accent: Option<AccentsColor>,
+        /// Only show presets whose author contains this text
+        #[arg(long)]
+        author: Option<String>,
+        /// Only show presets whose license contains this text
+        #[arg(long)]
+        license: Option<String>,
+        #[arg(long, value_enum, default_value_t = ListFormat::Table)]
+        format: ListFormat,
+    },
+    /// Check the store and list the files which couldn't be loaded
+    Doctor,
+    /// Download a preset from the first remote which has it
     Download {
-        /// The name of the preset to download
+        /// The name of the preset to download, it can be prefixed with a remote, e.g.
+        /// community:nord
+        name: String,
+    },
+    /// Add a remote to download presets from
+    AddRemote {
+        /// The name of the remote
+        name: String,
+        /// A GitHub repository like https://github.com/owner/repo/tree/branch, a file://
+        /// directory, or the URL of a JSON list of preset files relative to it
+        url: String,
+        /// Remotes with a higher priority are searched first
+        #[arg(long, default_value_t = 0, allow_negative_numbers = true)]
+        priority: i32,
+    },
+    /// Remove a remote
+    RemoveRemote {
+        /// The name of the remote
         name: String,
     },
+    /// List the remotes, the ones searched first first
+    ListRemotes,
+}
+
+#[derive(Subcommand, Debug)]
+enum GdmCommands {
+    /// Build the GDM resource in a directory without installing it
+    Preview {
+        /// The path to the directory where the theme and the resource will be written
+        output: Option<String>,
+    },
+    /// Build and install the GDM resource, the original one is backed up (usually requires root)
+    Install,
+    /// Restore the original GDM resource
+    Restore,
+}
+
+/// Returns the preset given with --preset, a preset file or a preset of the store
+fn load_preset(store: &Store, preset: &Option<String>) -> Preset {
+    let preset = preset.as_deref().expect("please provide --preset argument");
+    if std::path::Path::new(preset).is_file() {
+        return Preset::from_file(preset);
+    }
+    store
+        .get_preset(preset)
+        .unwrap_or_else(|e| panic!("{}", e))
+        .clone()
+}
+
+/// Reads the saved remotes, exits when the file can't be read
+fn load_remotes(path: &str) -> Vec<Remote> {
+    remote::load_remotes(path).unwrap_or_else(|e| {
+        eprintln!("Error reading the remotes from {}: {}", path, e);
+        std::process::exit(1);
+    })
+}
+
+fn save_remotes(path: &str, remotes: &[Remote]) {
+    if let Err(e) = remote::save_remotes(path, remotes) {
+        eprintln!("Error saving the remotes to {}: {}", path, e);
+        std::process::exit(1);
+    }
+}
+
+/// Reports an error caused by the user input (templates, sources...) and exits
+fn exit_on_error<T>(result: Result<T, std::io::Error>, action: &str) -> T {
+    result.unwrap_or_else(|e| {
+        eprintln!("Error {}: {}", action, e);
+        std::process::exit(1);
+    })
+}
+
+fn print_index(index: &[IndexEntry], format: ListFormat) {
+    if format == ListFormat::Json {
+        println!("{}", serde_json::to_string_pretty(index).unwrap());
+        return;
+    }
+
+    let mut rows = vec![[
+        "ID".to_string(),
+        "NAME".to_string(),
+        "VERSION".to_string(),
+        "AUTHOR".to_string(),
+        "LICENSE".to_string(),
+        "MODES".to_string(),
+        "ACCENTS".to_string(),
+        "LAYER".to_string(),
+        "PATH".to_string(),
+    ]];
+    for entry in index {
+        rows.push([
+            entry.id.clone(),
+            entry.name.clone(),
+            entry.version.clone(),
+            entry.author.clone(),
+            entry.license.clone(),
+            entry.modes.join(","),
+            entry.accents.join(","),
+            match entry.read_only {
+                true => entry.layer.clone(),
+                false => "user".to_string(),
+            },
+            entry.path.clone(),
+        ]);
+    }
+
+    let mut widths = [0; 9];
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    for row in rows {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{:width$}", cell, width = width))
+            .collect();
+        println!("{}", cells.join("  ").trim_end());
+    }
+}
+
+/// FNV-1a, unlike `DefaultHasher` its output doesn't change between Rust releases, so
+/// the extracted source is named the same by every build
+struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Fnv1a(0xcbf29ce484222325)
+    }
+}
+
+impl Hasher for Fnv1a {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ *byte as u64).wrapping_mul(0x100000001b3);
+        }
+    }
+}
+
+/// Feeds the paths and contents of the files to the hasher, with the bytes written
+/// directly since the `Hash` implementations of paths and slices may change
+fn hash_dir(dir: &Dir, hasher: &mut impl Hasher) {
+    for entry in dir.entries() {
+        match entry {
+            DirEntry::Dir(dir) => hash_dir(dir, hasher),
+            DirEntry::File(file) => {
+                // a path can't contain a NUL byte, and the length ends the contents
+                hasher.write(file.path().as_os_str().as_encoded_bytes());
+                hasher.write(&[0]);
+                hasher.write(&(file.contents().len() as u64).to_le_bytes());
+                hasher.write(file.contents());
+            }
+        }
+    }
+}
+
+/// Extracts the included shell source in the temporary directory and returns its path,
+/// the directory is named after the content hash so it's only extracted once per version,
+/// and it's extracted next to it then renamed so concurrent runs never see a partial copy
+fn extract_shell_source() -> Result<String, std::io::Error> {
+    let mut hasher = Fnv1a::default();
+    hash_dir(&SHELL_SOURCE, &mut hasher);
+    let name = format!("gradience-shell-{:016x}", hasher.finish());
+    let temp_dir = std::env::temp_dir();
+    let path = temp_dir.join(&name);
+    let source = path
+        .to_str()
+        .ok_or(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("{} isn't a UTF-8 path", path.display()),
+        ))?
+        .to_string();
+    if path.exists() {
+        return Ok(source);
+    }
+
+    let partial = temp_dir.join(format!("{}.{}", name, std::process::id()));
+    SHELL_SOURCE.extract(&partial)?;
+    if let Err(err) = std::fs::rename(&partial, &path) {
+        // another run extracted the same source first
+        std::fs::remove_dir_all(&partial)?;
+        if !path.exists() {
+            return Err(err);
+        }
+    }
+
+    Ok(source)
 }
 
 fn main() {
-    let args: Args = Args::parse();
+    let mut args: Args = Args::parse();
 
     if args.markdown_help {
         clap_markdown::print_help_markdown::<Args>();
         return;
     }
 
-    let mut store = Store::new(match args.store {
-        Some(path) => path,
-        None => DEFAULT_STORE_PATH.to_string(),
+    let config = Config::load(&match &args.config {
+        Some(path) => path.to_string(),
+        None => format!("{}/config.toml", config_dir()),
     });
-    store.load();
+    args.preset = args.preset.or(config.preset);
+    args.mode = args.mode.or(config.mode);
+    args.accent = args.accent.or(config.accent);
+    args.gtk3_path = args.gtk3_path.or(config.gtk3_path);
+    args.gtk4_path = args.gtk4_path.or(config.gtk4_path);
+    let default_theme_dir = config
+        .theme_dir
+        .unwrap_or(shellexpand::tilde("~/.themes").to_string());
+
+    let mut store = Store::new(match args.store.clone().or(config.store) {
+        Some(path) => path,
+        None => format!("{}/presets", data_dir()),
+    })
+    .layers(
+        system_stores()
+            .into_iter()
+            .rev()
+            .chain(config.store_layers)
+            .chain(
+                args.store_layer
+                    .iter()
+                    .map(|dir| shellexpand::tilde(dir).to_string()),
+            )
+            .collect(),
+    )
+    .recursive(args.store_recursive);
+    let report = store.load();
+    let doctor = matches!(
+        args.command,
+        Commands::Store {
+            command: StoreCommands::Doctor
+        }
+    );
+    if !report.errors.is_empty() && !doctor {
+        eprintln!(
+            "warning: {} file(s) of the store couldn't be loaded, run `gradience-cli store doctor` for details",
+            report.errors.len()
+        );
+    }
 
-    // copy everything from the included shell source to the current directory
-    SHELL_SOURCE
-        .extract(format!(
-            "{}/shell",
-            std::env::temp_dir().to_str().unwrap().to_string()
-        ))
-        .unwrap();
+    let remotes_path = format!("{}/remotes.json", config_dir());
+
+    let mut overlays = vec![format!("{}/shell", config_dir())];
+    overlays.extend(
+        args.shell_overlay
+            .iter()
+            .map(|dir| shellexpand::tilde(dir).to_string()),
+    );
 
     match &args.command {
         Commands::Shell {
@@ -163,23 +544,15 @@ fn main() {
             theme_dir,
             gsettings,
         } => {
-            Shell::new(
+            let result = Shell::new(
                 match args.shell_source {
                     Some(path) => path,
-                    None => format!(
-                        "{}/shell",
-                        std::env::temp_dir().to_str().unwrap().to_string()
-                    ),
+                    None => exit_on_error(extract_shell_source(), "extracting the shell source"),
                 },
-                store
-                    .get_preset(args.preset.expect("please provide --preset argument"))
-                    .unwrap_or_else(|| {
-                        panic!(
-                            "Unable to find this preset, use the name of the preset, not the filename"
-                        );
-                    })
-                    .clone(),
+                load_preset(&store, &args.preset),
             )
+            .overlays(overlays)
+            .strict(args.strict)
             .apply(
                 match temp_dir {
                     Some(dir) => dir.to_string(),
@@ -187,38 +560,278 @@ fn main() {
                 },
                 match theme_dir {
                     Some(dir) => dir.to_string(),
-                    None => shellexpand::tilde("~/.themes").to_string(),
+                    None => default_theme_dir.clone(),
                 },
                 args.mode.unwrap_or(Mode::Light).into(),
                 args.accent.unwrap_or(AccentsColor::Blue).into(),
-                gradience_lib::shell::ThemeName::Default,
+                ThemeName::Default,
                 *gsettings,
+            );
+            exit_on_error(result, "building the shell theme");
+        }
+        Commands::Build {
+            temp_dir,
+            theme_dir,
+            all_variants,
+            parallel,
+            shell_version,
+            gresource,
+        } => {
+            let shell = Shell::with_version(
+                match args.shell_source {
+                    Some(path) => path,
+                    None => exit_on_error(extract_shell_source(), "extracting the shell source"),
+                },
+                load_preset(&store, &args.preset),
+                match shell_version {
+                    Some(version) => ShellVersion::parse(version),
+                    None => get_gnome_shell_version(),
+                },
             )
-            .unwrap();
+            .overlays(overlays)
+            .strict(args.strict);
+            let temp_dir = match temp_dir {
+                Some(dir) => dir.to_string(),
+                None => std::env::temp_dir().to_str().unwrap().to_string(),
+            };
+            let theme_dir = match theme_dir {
+                Some(dir) => dir.to_string(),
+                None => default_theme_dir.clone(),
+            };
+
+            let themes = if *all_variants && *parallel {
+                shell.build_all_variants_parallel(&temp_dir, &theme_dir)
+            } else if *all_variants {
+                shell.build_all_variants(&temp_dir, &theme_dir)
+            } else {
+                shell.load_templates().and_then(|templates| {
+                    shell
+                        .build(
+                            &templates,
+                            &temp_dir,
+                            &theme_dir,
+                            args.mode.unwrap_or(Mode::Light).into(),
+                            args.accent.unwrap_or(AccentsColor::Blue).into(),
+                            &ThemeName::Default,
+                        )
+                        .map(|theme| vec![theme])
+                })
+            };
+
+            for theme in exit_on_error(themes, "building the shell theme") {
+                if *gresource {
+                    exit_on_error(
+                        Shell::build_gresource(
+                            &theme,
+                            &format!("{}/gnome-shell-theme.gresource", theme),
+                        ),
+                        "building the GResource bundle",
+                    );
+                }
+                println!("- {}", theme);
+            }
         }
         Commands::Gtk => {
-            ApplyBuilder::new(
-                store
-                    .get_preset(args.preset.expect("please provide --preset argument"))
-                    .unwrap_or_else(|| {
-                        panic!("Unable to find this preset, use the name of the preset, not the filename");
-                    })
-                    .clone(),
-            )
-            .mode(args.mode.unwrap_or(Mode::Light).into())
-            .accent(args.accent.unwrap_or(AccentsColor::Blue).into())
-            .gtk3_path(
-                shellexpand::tilde(args.gtk3_path.unwrap_or("~/.config/gtk-3.0/gtk.css".to_string()).as_str())
+            ApplyBuilder::new(load_preset(&store, &args.preset))
+                .mode(args.mode.unwrap_or(Mode::Light).into())
+                .accent(args.accent.unwrap_or(AccentsColor::Blue).into())
+                .gtk3_path(
+                    shellexpand::tilde(
+                        args.gtk3_path
+                            .unwrap_or("~/.config/gtk-3.0/gtk.css".to_string())
+                            .as_str(),
+                    )
                     .to_string()
                     .as_str(),
+                )
+                .gtk4_path(
+                    shellexpand::tilde(
+                        args.gtk4_path
+                            .unwrap_or("~/.config/gtk-4.0/gtk.css".to_string())
+                            .as_str(),
+                    )
+                    .to_string()
+                    .as_str(),
+                )
+                .apply();
+        }
+        Commands::ShellSources { shell_version } => {
+            let templates = source_layers(
+                &match args.shell_source {
+                    Some(path) => path,
+                    None => exit_on_error(extract_shell_source(), "extracting the shell source"),
+                },
+                &overlays,
+                match shell_version {
+                    Some(version) => ShellVersion::parse(version),
+                    None => get_gnome_shell_version(),
+                },
             )
-            .gtk4_path(
-                shellexpand::tilde(args.gtk4_path.unwrap_or("~/.config/gtk-4.0/gtk.css".to_string()).as_str())
-                                    .to_string()
-                                    .as_str(),
+            .and_then(|layers| ShellTemplates::load_layers(&layers));
+
+            for (file, layer) in exit_on_error(templates, "loading the shell sources").sources() {
+                println!("- {} ({})", file, layer);
+            }
+        }
+        Commands::CheckTemplates { shell_version } => {
+            let shell = Shell::with_version(
+                match args.shell_source {
+                    Some(path) => path,
+                    None => exit_on_error(extract_shell_source(), "extracting the shell source"),
+                },
+                load_preset(&store, &args.preset),
+                match shell_version {
+                    Some(version) => ShellVersion::parse(version),
+                    None => get_gnome_shell_version(),
+                },
             )
-            .apply();
+            .overlays(overlays);
+            let missing = exit_on_error(
+                shell
+                    .load_templates()
+                    .and_then(|templates| templates.check(&shell.preset)),
+                "checking the templates",
+            );
+
+            if missing.is_empty() {
+                println!("All template variables are provided by the preset");
+            } else {
+                for variable in missing {
+                    println!("- {}", variable);
+                }
+                std::process::exit(1);
+            }
+        }
+        Commands::ExportTheme { path, archive } => {
+            export_theme(&shellexpand::tilde(path), archive).unwrap();
+            println!("- {}", archive);
         }
+        Commands::ImportTheme { archive, theme_dir } => {
+            let theme_dir = match theme_dir {
+                Some(dir) => dir.to_string(),
+                None => default_theme_dir.clone(),
+            };
+            if !std::path::Path::new(&theme_dir).exists() {
+                std::fs::create_dir_all(&theme_dir).unwrap();
+            }
+            println!("- {}", import_theme(archive, &theme_dir).unwrap());
+        }
+        Commands::Gdm {
+            resource,
+            shell_version,
+            command,
+        } => {
+            let gdm = match resource {
+                Some(path) => Gdm::new().resource_path(path),
+                None => Gdm::new(),
+            };
+
+            let (output, install) = match command {
+                GdmCommands::Restore => {
+                    gdm.restore().unwrap();
+                    return;
+                }
+                GdmCommands::Preview { output } => (
+                    match output {
+                        Some(dir) => dir.to_string(),
+                        None => format!("{}/gradience-gdm", std::env::temp_dir().to_str().unwrap()),
+                    },
+                    false,
+                ),
+                GdmCommands::Install => (
+                    format!("{}/gradience-gdm", std::env::temp_dir().to_str().unwrap()),
+                    true,
+                ),
+            };
+
+            let shell = Shell::with_version(
+                match args.shell_source {
+                    Some(path) => path,
+                    None => exit_on_error(extract_shell_source(), "extracting the shell source"),
+                },
+                load_preset(&store, &args.preset),
+                match shell_version {
+                    Some(version) => ShellVersion::parse(version),
+                    None => get_gnome_shell_version(),
+                },
+            )
+            .overlays(overlays)
+            .strict(args.strict);
+            let theme = exit_on_error(
+                shell.load_templates().and_then(|templates| {
+                    shell.build(
+                        &templates,
+                        &format!("{}/build", output),
+                        &output,
+                        args.mode.unwrap_or(Mode::Dark).into(),
+                        args.accent.unwrap_or(AccentsColor::Blue).into(),
+                        &ThemeName::Default,
+                    )
+                }),
+                "building the shell theme",
+            );
+
+            let staged = format!("{}/gnome-shell-theme.gresource", output);
+            for path in gdm.stage(&theme, &staged).unwrap() {
+                println!("- {}", path);
+            }
+
+            if install {
+                gdm.install(&staged).unwrap();
+                println!("Installed {}, use `gdm restore` to undo", staged);
+            } else {
+                println!("Staged {}", staged);
+            }
+        }
+        Commands::Fmt {
+            paths,
+            omit_defaults,
+            check,
+        } => {
+            let mut unformatted = false;
+            for path in paths {
+                let content = std::fs::read_to_string(path).unwrap();
+                let preset = Preset::from_str(&content);
+                let formatted = match omit_defaults {
+                    true => preset.to_str_without_defaults(),
+                    false => preset.to_str(),
+                } + "\n";
+
+                if formatted == content {
+                    continue;
+                }
+                unformatted = true;
+                println!("- {}", path);
+                if !check {
+                    std::fs::write(path, formatted).unwrap();
+                }
+            }
+
+            if *check && unformatted {
+                std::process::exit(1);
+            }
+        }
+        Commands::Migrate { paths, dry_run } => {
+            for path in paths {
+                let content = std::fs::read_to_string(path).unwrap();
+                let (preset, report) = Preset::from_str_migrated(&content).unwrap();
+                if report.is_empty() {
+                    continue;
+                }
+
+                println!("- {}: schema {} -> {}", path, report.from, report.to);
+                for change in &report.changes {
+                    println!("  {}", change);
+                }
+                if !dry_run {
+                    preset.to_file(path);
+                }
+            }
+        }
+        Commands::Schema { output } => match output {
+            Some(output) => std::fs::write(output, Preset::json_schema() + "\n").unwrap(),
+            None => println!("{}", Preset::json_schema()),
+        },
         Commands::Store { command } => match command {
             StoreCommands::Add { path } => {
                 let preset: Preset = Preset::from_file(path);
@@ -226,25 +839,119 @@ fn main() {
                 store.save_presets();
             }
             StoreCommands::Remove { name } => {
-                store.remove_preset(name.to_string());
+                if let Err(e) = store.remove_preset(name) {
+                    eprintln!("Error removing {}: {}", name, e);
+                    std::process::exit(1);
+                }
             }
             StoreCommands::OnlineList => {
-                for p in store.list_online_presets() {
-                    println!("- {}", p);
+                for remote in remote::by_priority(&load_remotes(&remotes_path)) {
+                    match remote.list() {
+                        Ok(presets) => {
+                            for p in presets {
+                                println!("- {}:{}", p.remote, p.name);
+                            }
+                        }
+                        Err(e) => eprintln!("Error listing {}: {}", remote.name, e),
+                    }
                 }
             }
             StoreCommands::Download { name } => {
-                match store.download_online_preset(name.to_string()) {
+                match remote::download(&load_remotes(&remotes_path), name) {
                     Ok(preset) => store.add_preset(preset),
                     Err(e) => println!("Error downloading {}: {}", name, e),
                 }
                 store.save_presets();
             }
-            StoreCommands::LocalList => {
-                for p in store.list_local_presets() {
-                    println!("- {}", p);
+            StoreCommands::AddRemote {
+                name,
+                url,
+                priority,
+            } => {
+                let mut remotes = load_remotes(&remotes_path);
+                if remotes.iter().any(|r| r.name == *name) {
+                    eprintln!("A remote named {} already exists", name);
+                    std::process::exit(1);
+                }
+                match Remote::new(name, url) {
+                    Ok(remote) => remotes.push(remote.priority(*priority)),
+                    Err(e) => {
+                        eprintln!("Error adding {}: {}", name, e);
+                        std::process::exit(1);
+                    }
+                }
+                save_remotes(&remotes_path, &remotes);
+            }
+            StoreCommands::RemoveRemote { name } => {
+                let mut remotes = load_remotes(&remotes_path);
+                if !remotes.iter().any(|r| r.name == *name) {
+                    eprintln!("There is no remote named {}", name);
+                    std::process::exit(1);
+                }
+                remotes.retain(|r| r.name != *name);
+                save_remotes(&remotes_path, &remotes);
+            }
+            StoreCommands::ListRemotes => {
+                for remote in remote::by_priority(&load_remotes(&remotes_path)) {
+                    println!(
+                        "- {}: {} (priority {})",
+                        remote.name, remote.url, remote.priority
+                    );
                 }
             }
+            StoreCommands::Doctor => {
+                println!("{} preset(s) loaded", report.loaded);
+                for (path, error) in &report.errors {
+                    println!("- error: {}: {}", path, error);
+                }
+                for path in &report.skipped {
+                    println!("- skipped: {} isn't a preset", path);
+                }
+                for path in &report.hidden {
+                    println!(
+                        "- hidden: {} has the same ID as a preset of a later layer",
+                        path
+                    );
+                }
+                for path in &report.outdated {
+                    println!(
+                        "- outdated: {} uses an older preset format, run `gradience-cli migrate`",
+                        path
+                    );
+                }
+
+                let mut ids: BTreeMap<String, Vec<String>> = BTreeMap::new();
+                for entry in store.index() {
+                    ids.entry(entry.id).or_default().push(entry.path);
+                }
+                for (id, paths) in ids.iter().filter(|(_, paths)| paths.len() > 1) {
+                    println!(
+                        "- duplicate: {} have the same ID {}, use their filename to select them",
+                        paths.join(", "),
+                        id
+                    );
+                }
+
+                if !report.errors.is_empty() {
+                    std::process::exit(1);
+                }
+            }
+            StoreCommands::List { format } => print_index(&store.index(), *format),
+            StoreCommands::Search {
+                query,
+                supports,
+                accent,
+                author,
+                license,
+                format,
+            } => {
+                let search = Search::new(query.as_deref().unwrap_or_default())
+                    .mode(supports.map(|mode| format!("{:?}", mode).to_lowercase()))
+                    .accent(accent.map(|accent| format!("{:?}", accent).to_lowercase()))
+                    .author(author.clone())
+                    .license(license.clone());
+                print_index(&store.search(&search), *format);
+            }
         },
     }
 }
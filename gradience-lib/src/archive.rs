@@ -0,0 +1,212 @@
+use crate::shell::THEME_LAYOUT;
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+use walkdir::WalkDir;
+
+#[derive(Debug, Clone, Copy)]
+pub enum ArchiveFormat {
+    TarXz,
+    Zip,
+}
+
+impl ArchiveFormat {
+    /// Guesses the format from the archive extension (`.tar.xz`, `.txz` or `.zip`)
+    pub fn from_path(path: &str) -> Option<ArchiveFormat> {
+        let path = path.to_lowercase();
+        if path.ends_with(".tar.xz") || path.ends_with(".txz") {
+            Some(ArchiveFormat::TarXz)
+        } else if path.ends_with(".zip") {
+            Some(ArchiveFormat::Zip)
+        } else {
+            None
+        }
+    }
+}
+
+/// Returns the file name of an archive without its extension, whatever its case
+fn archive_stem(archive_path: &str) -> Option<String> {
+    let name = Path::new(archive_path).file_name()?.to_str()?;
+    [".tar.xz", ".txz", ".zip"].iter().find_map(|extension| {
+        let stem = name.len().checked_sub(extension.len())?;
+        name.get(stem..)?
+            .eq_ignore_ascii_case(extension)
+            .then(|| name[..stem].to_string())
+    })
+}
+
+/// Returns the path of an archive entry without its `./` components, archives made with
+/// `tar -C dir .` prefix every entry with them, paths leaving the theme are rejected
+fn entry_path(path: &Path) -> Result<PathBuf, std::io::Error> {
+    let mut relative = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => relative.push(part),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("Invalid path in archive: {}", path.display()),
+                ))
+            }
+        }
+    }
+    Ok(relative)
+}
+
+fn unknown_format(path: &str) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        format!("Unknown archive format for {}, use .tar.xz or .zip", path),
+    )
+}
+
+/// Packs a built theme in an archive, the theme is stored in a top level directory
+/// named after the theme so it can be extracted directly in `~/.themes`
+pub fn export_theme(theme_dir: &str, archive_path: &str) -> Result<(), std::io::Error> {
+    let format = ArchiveFormat::from_path(archive_path).ok_or(unknown_format(archive_path))?;
+    let theme_dir = Path::new(theme_dir);
+    let name = theme_dir
+        .file_name()
+        .ok_or(std::io::Error::other("Invalid theme directory"))?;
+
+    let mut files = Vec::new();
+    for part in THEME_LAYOUT {
+        let path = theme_dir.join(part);
+        if !path.exists() {
+            continue;
+        }
+
+        for entry in WalkDir::new(&path).sort_by_file_name() {
+            let entry = entry?;
+            if entry.file_type().is_file() {
+                let relative = entry.path().strip_prefix(theme_dir).unwrap();
+                files.push((Path::new(name).join(relative), entry.path().to_path_buf()));
+            }
+        }
+    }
+
+    if files.is_empty() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("{} doesn't contain a theme", theme_dir.display()),
+        ));
+    }
+
+    let file = std::fs::File::create(archive_path)?;
+    match format {
+        ArchiveFormat::TarXz => {
+            let mut builder = tar::Builder::new(xz2::write::XzEncoder::new(file, 6));
+            for (name, path) in files {
+                builder.append_path_with_name(path, name)?;
+            }
+            builder.into_inner()?.finish()?;
+        }
+        ArchiveFormat::Zip => {
+            let mut writer = zip::ZipWriter::new(file);
+            let options = zip::write::SimpleFileOptions::default();
+            for (name, path) in files {
+                writer.start_file(name.to_str().unwrap(), options)?;
+                writer.write_all(&std::fs::read(path)?)?;
+            }
+            writer.finish()?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Installs a theme archive in `themes_dir` and returns the path of the installed theme,
+/// an existing theme with the same name is replaced
+pub fn import_theme(archive_path: &str, themes_dir: &str) -> Result<String, std::io::Error> {
+    let format = ArchiveFormat::from_path(archive_path).ok_or(unknown_format(archive_path))?;
+    let file = std::fs::File::open(archive_path)?;
+
+    let mut files = Vec::new();
+    match format {
+        ArchiveFormat::TarXz => {
+            let mut archive = tar::Archive::new(xz2::read::XzDecoder::new(file));
+            for entry in archive.entries()? {
+                let mut entry = entry?;
+                if !entry.header().entry_type().is_file() {
+                    continue;
+                }
+                let path = entry_path(&entry.path()?)?;
+                let mut content = Vec::new();
+                entry.read_to_end(&mut content)?;
+                files.push((path, content));
+            }
+        }
+        ArchiveFormat::Zip => {
+            let mut archive = zip::ZipArchive::new(file)?;
+            for i in 0..archive.len() {
+                let mut entry = archive.by_index(i)?;
+                if !entry.is_file() {
+                    continue;
+                }
+                let path = entry_path(Path::new(entry.name()))?;
+                let mut content = Vec::new();
+                entry.read_to_end(&mut content)?;
+                files.push((path, content));
+            }
+        }
+    }
+
+    // themes are either stored at the root of the archive or in a single directory,
+    // anything outside of the theme layout (README, other desktops...) is skipped
+    let is_layout = |path: &Path| {
+        THEME_LAYOUT
+            .iter()
+            .any(|part| path.components().next() == Some(Component::Normal(part.as_ref())))
+    };
+    let root = if files.iter().any(|(path, _)| is_layout(path)) {
+        None
+    } else {
+        files.iter().find_map(|(path, _)| {
+            let mut components = path.components();
+            let root = components.next()?;
+            is_layout(components.as_path()).then(|| root.as_os_str().to_os_string())
+        })
+    };
+    let name = match &root {
+        Some(root) => root.to_str().unwrap().to_string(),
+        None => archive_stem(archive_path).unwrap_or_default(),
+    };
+    if matches!(name.as_str(), "" | "." | "..") {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("Can't name a theme after {}", archive_path),
+        ));
+    }
+    let files: Vec<(PathBuf, Vec<u8>)> = files
+        .into_iter()
+        .filter_map(|(path, content)| {
+            let path = match &root {
+                Some(root) => path.strip_prefix(root).ok()?.to_path_buf(),
+                None => path,
+            };
+            is_layout(&path).then_some((path, content))
+        })
+        .collect();
+
+    if files.is_empty() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("{} doesn't contain a theme", archive_path),
+        ));
+    }
+
+    let theme_dir = format!("{}/{}", themes_dir, name);
+    if Path::new(&theme_dir).exists() {
+        std::fs::remove_dir_all(&theme_dir)?;
+    }
+
+    for (path, content) in files {
+        let path = Path::new(&theme_dir).join(path);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, content)?;
+    }
+
+    Ok(theme_dir)
+}
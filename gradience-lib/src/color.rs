@@ -0,0 +1,225 @@
+/// A color parsed from a CSS value, channels are between 0 and 1
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Color {
+    pub red: f64,
+    pub green: f64,
+    pub blue: f64,
+    pub alpha: f64,
+}
+
+impl Color {
+    pub fn new(red: f64, green: f64, blue: f64, alpha: f64) -> Color {
+        Color {
+            red: red.clamp(0.0, 1.0),
+            green: green.clamp(0.0, 1.0),
+            blue: blue.clamp(0.0, 1.0),
+            alpha: alpha.clamp(0.0, 1.0),
+        }
+    }
+
+    /// Parses `#rgb`, `#rgba`, `#rrggbb`, `#rrggbbaa`, `rgb()`, `rgba()`, `white`,
+    /// `black` and `transparent`
+    pub fn parse(value: &str) -> Option<Color> {
+        let value = value.trim().to_lowercase();
+
+        match value.as_str() {
+            "white" => return Some(Color::new(1.0, 1.0, 1.0, 1.0)),
+            "black" => return Some(Color::new(0.0, 0.0, 0.0, 1.0)),
+            "transparent" => return Some(Color::new(0.0, 0.0, 0.0, 0.0)),
+            _ => {}
+        }
+
+        if let Some(hex) = value.strip_prefix('#') {
+            let digits: Vec<u8> = hex
+                .chars()
+                .map(|c| c.to_digit(16).map(|d| d as u8))
+                .collect::<Option<Vec<u8>>>()?;
+            let channels: Vec<f64> = match digits.len() {
+                3 | 4 => digits.iter().map(|d| (d * 17) as f64 / 255.0).collect(),
+                6 | 8 => digits
+                    .chunks(2)
+                    .map(|d| (d[0] * 16 + d[1]) as f64 / 255.0)
+                    .collect(),
+                _ => return None,
+            };
+            return Some(Color::new(
+                channels[0],
+                channels[1],
+                channels[2],
+                *channels.get(3).unwrap_or(&1.0),
+            ));
+        }
+
+        let args = value
+            .strip_prefix("rgba(")
+            .or(value.strip_prefix("rgb("))?
+            .strip_suffix(')')?;
+        let args: Vec<&str> = args.split(',').map(|a| a.trim()).collect();
+        if args.len() != 3 && args.len() != 4 {
+            return None;
+        }
+
+        let channel = |a: &str| match a.strip_suffix('%') {
+            Some(percent) => percent.parse::<f64>().ok().map(|p| p / 100.0),
+            None => a.parse::<f64>().ok().map(|c| c / 255.0),
+        };
+        let alpha = match args.get(3) {
+            Some(a) => match a.strip_suffix('%') {
+                Some(percent) => percent.parse::<f64>().ok()? / 100.0,
+                None => a.parse::<f64>().ok()?,
+            },
+            None => 1.0,
+        };
+
+        Some(Color::new(
+            channel(args[0])?,
+            channel(args[1])?,
+            channel(args[2])?,
+            alpha,
+        ))
+    }
+
+    /// Returns `#rrggbb`, or `rgba()` when the color is translucent
+    pub fn to_css(&self) -> String {
+        if self.alpha < 1.0 {
+            return self.to_rgba();
+        }
+
+        format!(
+            "#{:02x}{:02x}{:02x}",
+            (self.red * 255.0).round() as u8,
+            (self.green * 255.0).round() as u8,
+            (self.blue * 255.0).round() as u8
+        )
+    }
+
+    pub fn to_rgba(&self) -> String {
+        format!(
+            "rgba({}, {}, {}, {})",
+            (self.red * 255.0).round() as u8,
+            (self.green * 255.0).round() as u8,
+            (self.blue * 255.0).round() as u8,
+            (self.alpha * 1000.0).round() / 1000.0
+        )
+    }
+
+    fn to_hsl(self) -> (f64, f64, f64) {
+        let max = self.red.max(self.green).max(self.blue);
+        let min = self.red.min(self.green).min(self.blue);
+        let lightness = (max + min) / 2.0;
+
+        if max == min {
+            return (0.0, 0.0, lightness);
+        }
+
+        let delta = max - min;
+        let saturation = if lightness > 0.5 {
+            delta / (2.0 - max - min)
+        } else {
+            delta / (max + min)
+        };
+        let hue = if max == self.red {
+            (self.green - self.blue) / delta + if self.green < self.blue { 6.0 } else { 0.0 }
+        } else if max == self.green {
+            (self.blue - self.red) / delta + 2.0
+        } else {
+            (self.red - self.green) / delta + 4.0
+        };
+
+        (hue / 6.0, saturation, lightness)
+    }
+
+    fn from_hsl(hue: f64, saturation: f64, lightness: f64, alpha: f64) -> Color {
+        if saturation == 0.0 {
+            return Color::new(lightness, lightness, lightness, alpha);
+        }
+
+        let q = if lightness < 0.5 {
+            lightness * (1.0 + saturation)
+        } else {
+            lightness + saturation - lightness * saturation
+        };
+        let p = 2.0 * lightness - q;
+        let channel = |t: f64| {
+            let t = t.rem_euclid(1.0);
+            if t < 1.0 / 6.0 {
+                p + (q - p) * 6.0 * t
+            } else if t < 0.5 {
+                q
+            } else if t < 2.0 / 3.0 {
+                p + (q - p) * (2.0 / 3.0 - t) * 6.0
+            } else {
+                p
+            }
+        };
+
+        Color::new(
+            channel(hue + 1.0 / 3.0),
+            channel(hue),
+            channel(hue - 1.0 / 3.0),
+            alpha,
+        )
+    }
+
+    /// Increases the lightness by `amount` percent, like SCSS `lighten()`
+    pub fn lighten(&self, amount: f64) -> Color {
+        let (hue, saturation, lightness) = self.to_hsl();
+        Color::from_hsl(
+            hue,
+            saturation,
+            (lightness + amount / 100.0).clamp(0.0, 1.0),
+            self.alpha,
+        )
+    }
+
+    /// Decreases the lightness by `amount` percent, like SCSS `darken()`
+    pub fn darken(&self, amount: f64) -> Color {
+        self.lighten(-amount)
+    }
+
+    /// Mixes two colors, `weight` is the percentage of `self`, like SCSS `mix()`
+    pub fn mix(&self, other: &Color, weight: f64) -> Color {
+        let p = (weight / 100.0).clamp(0.0, 1.0);
+        let w = 2.0 * p - 1.0;
+        let a = self.alpha - other.alpha;
+        let w1 = (if w * a == -1.0 { w } else { (w + a) / (1.0 + w * a) } + 1.0) / 2.0;
+        let w2 = 1.0 - w1;
+
+        Color::new(
+            self.red * w1 + other.red * w2,
+            self.green * w1 + other.green * w2,
+            self.blue * w1 + other.blue * w2,
+            self.alpha * p + other.alpha * (1.0 - p),
+        )
+    }
+
+    pub fn with_alpha(&self, alpha: f64) -> Color {
+        Color::new(self.red, self.green, self.blue, alpha)
+    }
+
+    /// Relative luminance as defined by WCAG
+    pub fn luminance(&self) -> f64 {
+        let linear = |c: f64| {
+            if c <= 0.03928 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        0.2126 * linear(self.red) + 0.7152 * linear(self.green) + 0.0722 * linear(self.blue)
+    }
+
+    /// Returns true for light colors, the threshold keeps white text on Adwaita accents
+    pub fn is_light(&self) -> bool {
+        self.luminance() > 0.5
+    }
+
+    /// Returns the Adwaita foreground color to use on this color
+    pub fn contrast_fg(&self) -> String {
+        if self.is_light() {
+            "rgba(0, 0, 0, 0.8)".to_string()
+        } else {
+            "#ffffff".to_string()
+        }
+    }
+}
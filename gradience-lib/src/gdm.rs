@@ -0,0 +1,101 @@
+use crate::gresource::GResourceBuilder;
+use crate::shell::Shell;
+
+/// Where most distributions install the theme used by GDM
+pub const GDM_RESOURCE_PATH: &str = "/usr/share/gnome-shell/gnome-shell-theme.gresource";
+
+/// Themes the GDM greeter by replacing the shell theme resource, the original resource
+/// is backed up next to it so it can be restored
+pub struct Gdm {
+    resource_path: String,
+}
+
+impl Default for Gdm {
+    fn default() -> Self {
+        Gdm::new()
+    }
+}
+
+impl Gdm {
+    pub fn new() -> Gdm {
+        Gdm {
+            resource_path: GDM_RESOURCE_PATH.to_string(),
+        }
+    }
+
+    pub fn resource_path(mut self, path: &str) -> Gdm {
+        self.resource_path = path.to_string();
+        self
+    }
+
+    pub fn backup_path(&self) -> String {
+        format!("{}.gradience-backup", self.resource_path)
+    }
+
+    /// Marks a resource installed where there was none, so restoring removes it
+    fn created_path(&self) -> String {
+        format!("{}.gradience-created", self.resource_path)
+    }
+
+    /// Returns true when a Gradience theme is installed, i.e. there is something to restore
+    pub fn is_installed(&self) -> bool {
+        std::path::Path::new(&self.backup_path()).exists()
+            || std::path::Path::new(&self.created_path()).exists()
+    }
+
+    /// Builds the resource for a built theme in `output` without touching the system, the
+    /// original resource is used as a base so the assets the theme doesn't provide are kept.
+    /// Returns the paths of the files in the resource
+    pub fn stage(&self, theme_dir: &str, output: &str) -> Result<Vec<String>, std::io::Error> {
+        let original = if std::path::Path::new(&self.created_path()).exists() {
+            None
+        } else if self.is_installed() {
+            Some(self.backup_path())
+        } else {
+            Some(self.resource_path.clone())
+        };
+
+        let base = match original.filter(|path| std::path::Path::new(path).exists()) {
+            Some(original) => GResourceBuilder::from_bytes(&std::fs::read(original)?)?,
+            None => GResourceBuilder::new(),
+        };
+
+        let builder = Shell::add_to_gresource(theme_dir, base)?;
+        builder.write(output)?;
+        Ok(builder.paths())
+    }
+
+    /// Installs a staged resource, the original one is backed up the first time
+    pub fn install(&self, staged: &str) -> Result<(), std::io::Error> {
+        if !self.is_installed() {
+            if std::path::Path::new(&self.resource_path).exists() {
+                std::fs::copy(&self.resource_path, self.backup_path())?;
+            } else {
+                std::fs::write(self.created_path(), "")?;
+            }
+        }
+
+        // write next to the resource first so GDM never reads a partial file
+        let temp = format!("{}.gradience-new", self.resource_path);
+        std::fs::copy(staged, &temp)?;
+        std::fs::rename(&temp, &self.resource_path)
+    }
+
+    /// Puts the original resource back, or removes the installed one when there was none
+    pub fn restore(&self) -> Result<(), std::io::Error> {
+        if std::path::Path::new(&self.created_path()).exists() {
+            if std::path::Path::new(&self.resource_path).exists() {
+                std::fs::remove_file(&self.resource_path)?;
+            }
+            return std::fs::remove_file(self.created_path());
+        }
+        if !self.is_installed() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("No backup of {} to restore", self.resource_path),
+            ));
+        }
+
+        std::fs::rename(self.backup_path(), &self.resource_path)
+    }
+}
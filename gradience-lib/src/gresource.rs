@@ -0,0 +1,313 @@
+use std::collections::BTreeMap;
+
+/// Builds a GResource bundle (the format written by `glib-compile-resources`)
+/// without needing GLib, added files are stored uncompressed
+pub struct GResourceBuilder {
+    files: BTreeMap<String, Resource>,
+}
+
+/// A file as stored in the bundle, `data` is compressed when `flags` says so
+#[derive(PartialEq)]
+struct Resource {
+    size: u32,
+    flags: u32,
+    data: Vec<u8>,
+}
+
+struct Item {
+    key: String,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    data: Option<usize>,
+}
+
+const HASH_ITEM_SIZE: usize = 24;
+const NO_PARENT: u32 = 0xffffffff;
+
+/// Hash used by GVDB to find keys, bytes are signed like C `char`
+fn djb_hash(key: &str) -> u32 {
+    key.bytes()
+        .fold(5381u32, |hash, b| hash.wrapping_mul(33).wrapping_add(b as i8 as u32))
+}
+
+fn align(data: &mut Vec<u8>, alignment: usize) {
+    data.resize(data.len().next_multiple_of(alignment), 0);
+}
+
+fn push_u32(data: &mut Vec<u8>, value: u32) {
+    data.extend_from_slice(&value.to_le_bytes());
+}
+
+/// Returns `len` bytes at `offset`, corrupt offsets are errors instead of panics
+fn read_bytes(data: &[u8], offset: usize, len: usize) -> Result<&[u8], std::io::Error> {
+    offset
+        .checked_add(len)
+        .and_then(|end| data.get(offset..end))
+        .ok_or(invalid_data())
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, std::io::Error> {
+    read_bytes(data, offset, 4).map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16, std::io::Error> {
+    read_bytes(data, offset, 2).map(|bytes| u16::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+fn invalid_data() -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, "Invalid GResource file")
+}
+
+impl Default for GResourceBuilder {
+    fn default() -> Self {
+        GResourceBuilder::new()
+    }
+}
+
+impl GResourceBuilder {
+    pub fn new() -> GResourceBuilder {
+        GResourceBuilder {
+            files: BTreeMap::new(),
+        }
+    }
+
+    /// Reads an existing bundle, its files are kept as is and can be replaced with [`GResourceBuilder::file`]
+    pub fn from_bytes(bytes: &[u8]) -> Result<GResourceBuilder, std::io::Error> {
+        if bytes.get(0..8) != Some(b"GVariant".as_slice()) {
+            return Err(invalid_data());
+        }
+
+        let root_start = read_u32(bytes, 16)? as usize;
+        let root_end = read_u32(bytes, 20)? as usize;
+        let n_bloom_words = (read_u32(bytes, root_start)? & ((1 << 27) - 1)) as usize;
+        let n_buckets = read_u32(bytes, root_start + 4)? as usize;
+        let items_start = n_bloom_words
+            .checked_add(n_buckets)
+            .and_then(|words| words.checked_mul(4))
+            .and_then(|size| size.checked_add(root_start + 8))
+            .ok_or(invalid_data())?;
+        if root_end > bytes.len() || items_start > root_end {
+            return Err(invalid_data());
+        }
+        let n_items = (root_end - items_start) / HASH_ITEM_SIZE;
+
+        let item = |i: usize| -> Result<(u32, String, u8, usize, usize), std::io::Error> {
+            if i >= n_items {
+                return Err(invalid_data());
+            }
+            let offset = items_start + i * HASH_ITEM_SIZE;
+            let parent = read_u32(bytes, offset + 4)?;
+            let key_start = read_u32(bytes, offset + 8)? as usize;
+            let key_size = read_u16(bytes, offset + 12)? as usize;
+            let key = read_bytes(bytes, key_start, key_size)?;
+            Ok((
+                parent,
+                String::from_utf8_lossy(key).to_string(),
+                read_bytes(bytes, offset + 14, 1)?[0],
+                read_u32(bytes, offset + 16)? as usize,
+                read_u32(bytes, offset + 20)? as usize,
+            ))
+        };
+
+        let mut files = BTreeMap::new();
+        for i in 0..n_items {
+            let (mut parent, mut path, kind, start, end) = item(i)?;
+            if kind != b'v' {
+                continue;
+            }
+
+            // keys are stored relative to their parent directory
+            let mut depth = 0;
+            while parent != NO_PARENT && depth < n_items {
+                let (next, key, _, _, _) = item(parent as usize)?;
+                path = key + &path;
+                parent = next;
+                depth += 1;
+            }
+
+            // `v` variant holding `(uuay)`, the type string follows the last nul byte
+            let value = read_bytes(bytes, start, end.checked_sub(start).ok_or(invalid_data())?)?;
+            let split = value.iter().rposition(|b| *b == 0).ok_or(invalid_data())?;
+            if &value[split + 1..] != b"(uuay)" || split < 8 {
+                return Err(invalid_data());
+            }
+            files.insert(
+                path,
+                Resource {
+                    size: read_u32(value, 0)?,
+                    flags: read_u32(value, 4)?,
+                    data: value[8..split].to_vec(),
+                },
+            );
+        }
+
+        Ok(GResourceBuilder { files })
+    }
+
+    /// Adds a file at an absolute resource path, e.g. `/org/gnome/shell/theme/gnome-shell.css`
+    pub fn file(mut self, path: &str, data: Vec<u8>) -> GResourceBuilder {
+        let size = data.len() as u32;
+        let mut data = data;
+        // uncompressed files are nul terminated, like glib-compile-resources does
+        data.push(0);
+        self.files.insert(path.to_string(), Resource { size, flags: 0, data });
+        self
+    }
+
+    /// Returns the paths of the files in the bundle
+    pub fn paths(&self) -> Vec<String> {
+        self.files.keys().cloned().collect()
+    }
+
+    pub fn build(&self) -> Vec<u8> {
+        // every file and parent directory is an item, directories list their children
+        let mut items: Vec<Item> = Vec::new();
+        let mut index: BTreeMap<String, usize> = BTreeMap::new();
+        let mut values: Vec<&Resource> = Vec::new();
+
+        for (path, content) in &self.files {
+            let mut parent = None;
+            let mut end = 0;
+            while let Some(pos) = path[end..].find('/') {
+                end += pos + 1;
+                let dir = &path[..end];
+                let item = match index.get(dir) {
+                    Some(item) => *item,
+                    None => {
+                        items.push(Item {
+                            key: dir.to_string(),
+                            parent,
+                            children: Vec::new(),
+                            data: None,
+                        });
+                        let item = items.len() - 1;
+                        index.insert(dir.to_string(), item);
+                        if let Some(parent) = parent {
+                            items[parent].children.push(item);
+                        }
+                        item
+                    }
+                };
+                parent = Some(item);
+            }
+
+            // identical files share the same data
+            let data = match values.iter().position(|value| *value == content) {
+                Some(data) => data,
+                None => {
+                    values.push(content);
+                    values.len() - 1
+                }
+            };
+            items.push(Item {
+                key: path.to_string(),
+                parent,
+                children: Vec::new(),
+                data: Some(data),
+            });
+            let item = items.len() - 1;
+            if let Some(parent) = parent {
+                items[parent].children.push(item);
+            }
+        }
+
+        // items are stored sorted by bucket
+        let n_buckets = items.len() as u32;
+        let mut order: Vec<usize> = (0..items.len()).collect();
+        order.sort_by_key(|i| djb_hash(&items[*i].key) % n_buckets.max(1));
+        let mut position = vec![0; items.len()];
+        for (pos, i) in order.iter().enumerate() {
+            position[*i] = pos;
+        }
+
+        let header_size = 24;
+        let table_size = 8 + 4 * items.len() + HASH_ITEM_SIZE * items.len();
+
+        let mut out = Vec::new();
+        out.extend_from_slice(b"GVariant");
+        push_u32(&mut out, 0);
+        push_u32(&mut out, 0);
+        push_u32(&mut out, header_size as u32);
+        push_u32(&mut out, (header_size + table_size) as u32);
+        out.resize(header_size + table_size, 0);
+
+        // file contents as a `v` variant holding `(uuay)`: size, flags and data
+        let mut value_pointers = Vec::new();
+        for resource in &values {
+            align(&mut out, 8);
+            let start = out.len();
+            push_u32(&mut out, resource.size);
+            push_u32(&mut out, resource.flags);
+            out.extend_from_slice(&resource.data);
+            out.push(0);
+            out.extend_from_slice(b"(uuay)");
+            value_pointers.push((start as u32, out.len() as u32));
+        }
+
+        let mut buckets = vec![items.len() as u32; items.len()];
+        let mut entries = Vec::new();
+        for (pos, i) in order.iter().enumerate() {
+            let item = &items[*i];
+            let hash = djb_hash(&item.key);
+            let bucket = (hash % n_buckets) as usize;
+            if buckets[bucket] as usize > pos {
+                buckets[bucket] = pos as u32;
+            }
+
+            let name = match item.parent {
+                Some(parent) => &item.key[items[parent].key.len()..],
+                None => item.key.as_str(),
+            };
+            let key_start = out.len() as u32;
+            out.extend_from_slice(name.as_bytes());
+
+            let (kind, value) = match item.data {
+                Some(data) => (b'v', value_pointers[data]),
+                None => {
+                    align(&mut out, 4);
+                    let start = out.len() as u32;
+                    for child in &item.children {
+                        push_u32(&mut out, position[*child] as u32);
+                    }
+                    (b'L', (start, out.len() as u32))
+                }
+            };
+
+            let mut entry = Vec::with_capacity(HASH_ITEM_SIZE);
+            push_u32(&mut entry, hash);
+            push_u32(
+                &mut entry,
+                item.parent.map(|p| position[p] as u32).unwrap_or(NO_PARENT),
+            );
+            push_u32(&mut entry, key_start);
+            entry.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            entry.push(kind);
+            entry.push(0);
+            push_u32(&mut entry, value.0);
+            push_u32(&mut entry, value.1);
+            entries.extend(entry);
+        }
+
+        // empty buckets point to the first item of the next bucket
+        for bucket in (0..buckets.len().saturating_sub(1)).rev() {
+            if buckets[bucket] > buckets[bucket + 1] {
+                buckets[bucket] = buckets[bucket + 1];
+            }
+        }
+
+        let mut table = Vec::with_capacity(table_size);
+        push_u32(&mut table, 0);
+        push_u32(&mut table, n_buckets);
+        for bucket in buckets {
+            push_u32(&mut table, bucket);
+        }
+        table.extend(entries);
+        out[header_size..header_size + table_size].copy_from_slice(&table);
+
+        out
+    }
+
+    pub fn write(&self, path: &str) -> Result<(), std::io::Error> {
+        std::fs::write(path, self.build())
+    }
+}
@@ -0,0 +1,183 @@
+use crate::color::Color;
+use handlebars::{
+    no_escape, Context, Handlebars, Helper, HelperResult, Output, RenderContext,
+    RenderErrorReason, Renderable,
+};
+
+/// Returns a registry for templates producing SCSS, with the color helpers registered.
+/// Values aren't HTML escaped: SCSS uses `&`, `>` and quotes, e.g. `&:hover`, `a > b` or
+/// a quoted font family, which would otherwise be rendered as `&amp;`, `&gt;` and `&quot;`
+pub fn template_registry() -> Handlebars<'static> {
+    let mut reg = Handlebars::new();
+    reg.register_escape_fn(no_escape);
+    register_helpers(&mut reg);
+    reg
+}
+
+/// Registers the color helpers available to shell templates:
+///
+/// - `{{lighten accent_color 10}}` and `{{darken accent_color 10}}`
+/// - `{{mix accent_color bg_color 25}}`, the weight is the percentage of the first color
+/// - `{{alpha fg_color 0.5}}` and `{{to_rgba accent_color}}`
+/// - `{{contrast_fg accent_bg_color}}`, the Adwaita foreground readable on a color
+/// - `{{#if_light bg_color}}...{{else}}...{{/if_light}}`, without color it checks the mode
+///
+/// Colors can be any preset value, including `@name` references to other variables
+pub fn register_helpers(reg: &mut Handlebars) {
+    reg.register_helper("lighten", Box::new(lighten_helper));
+    reg.register_helper("darken", Box::new(darken_helper));
+    reg.register_helper("mix", Box::new(mix_helper));
+    reg.register_helper("alpha", Box::new(alpha_helper));
+    reg.register_helper("contrast_fg", Box::new(contrast_fg_helper));
+    reg.register_helper("to_rgba", Box::new(to_rgba_helper));
+    reg.register_helper("if_light", Box::new(if_light_helper));
+}
+
+fn color_param(
+    h: &Helper,
+    ctx: &Context,
+    helper: &'static str,
+    index: usize,
+) -> Result<Color, RenderErrorReason> {
+    let param = h
+        .param(index)
+        .ok_or(RenderErrorReason::ParamNotFoundForIndex(helper, index))?;
+    if param.is_value_missing() {
+        return Err(RenderErrorReason::MissingVariable(
+            param.relative_path().cloned(),
+        ));
+    }
+    let mut value = param
+        .value()
+        .as_str()
+        .ok_or(RenderErrorReason::InvalidParamType("color"))?
+        .to_string();
+
+    // follow `@name` references to other variables
+    let mut depth = 0;
+    while let Some(name) = value.strip_prefix('@') {
+        match ctx.data().get(name).and_then(|v| v.as_str()) {
+            Some(next) if depth < 8 => value = next.to_string(),
+            _ => break,
+        }
+        depth += 1;
+    }
+
+    Color::parse(&value).ok_or(RenderErrorReason::Other(format!(
+        "{}: unable to parse color {}",
+        helper, value
+    )))
+}
+
+fn number_param(h: &Helper, helper: &'static str, index: usize) -> Result<f64, RenderErrorReason> {
+    let param = h
+        .param(index)
+        .ok_or(RenderErrorReason::ParamNotFoundForIndex(helper, index))?;
+    let value = param.value();
+
+    value
+        .as_f64()
+        .or(value
+            .as_str()
+            .and_then(|s| s.trim().trim_end_matches('%').parse::<f64>().ok()))
+        .ok_or(RenderErrorReason::InvalidParamType("number"))
+}
+
+fn lighten_helper(
+    h: &Helper,
+    _: &Handlebars,
+    ctx: &Context,
+    _: &mut RenderContext,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let color = color_param(h, ctx, "lighten", 0)?;
+    let amount = number_param(h, "lighten", 1)?;
+    out.write(&color.lighten(amount).to_css())?;
+    Ok(())
+}
+
+fn darken_helper(
+    h: &Helper,
+    _: &Handlebars,
+    ctx: &Context,
+    _: &mut RenderContext,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let color = color_param(h, ctx, "darken", 0)?;
+    let amount = number_param(h, "darken", 1)?;
+    out.write(&color.darken(amount).to_css())?;
+    Ok(())
+}
+
+fn mix_helper(
+    h: &Helper,
+    _: &Handlebars,
+    ctx: &Context,
+    _: &mut RenderContext,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let color = color_param(h, ctx, "mix", 0)?;
+    let other = color_param(h, ctx, "mix", 1)?;
+    let weight = match h.param(2) {
+        Some(_) => number_param(h, "mix", 2)?,
+        None => 50.0,
+    };
+    out.write(&color.mix(&other, weight).to_css())?;
+    Ok(())
+}
+
+fn alpha_helper(
+    h: &Helper,
+    _: &Handlebars,
+    ctx: &Context,
+    _: &mut RenderContext,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let color = color_param(h, ctx, "alpha", 0)?;
+    let alpha = number_param(h, "alpha", 1)?;
+    out.write(&color.with_alpha(alpha).to_rgba())?;
+    Ok(())
+}
+
+fn contrast_fg_helper(
+    h: &Helper,
+    _: &Handlebars,
+    ctx: &Context,
+    _: &mut RenderContext,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let color = color_param(h, ctx, "contrast_fg", 0)?;
+    out.write(&color.contrast_fg())?;
+    Ok(())
+}
+
+fn to_rgba_helper(
+    h: &Helper,
+    _: &Handlebars,
+    ctx: &Context,
+    _: &mut RenderContext,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let color = color_param(h, ctx, "to_rgba", 0)?;
+    out.write(&color.to_rgba())?;
+    Ok(())
+}
+
+fn if_light_helper<'reg, 'rc>(
+    h: &Helper<'rc>,
+    r: &'reg Handlebars<'reg>,
+    ctx: &'rc Context,
+    rc: &mut RenderContext<'reg, 'rc>,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let light = match h.param(0) {
+        Some(_) => color_param(h, ctx, "if_light", 0)?.is_light(),
+        None => ctx.data().get("mode").and_then(|m| m.as_str()) == Some("light"),
+    };
+
+    let template = if light { h.template() } else { h.inverse() };
+    match template {
+        Some(t) => t.render(r, ctx, rc, out),
+        None => Ok(()),
+    }
+}
@@ -1,4 +1,13 @@
+#[cfg(feature = "archive")]
+pub mod archive;
+pub mod color;
+pub mod gdm;
+pub mod gresource;
+pub mod helpers;
+pub mod migration;
 pub mod preset;
+pub mod remote;
 pub mod shell;
 pub mod store;
+pub mod tokens;
 pub mod utils;
@@ -0,0 +1,137 @@
+use serde_json::{Map, Value};
+
+/// Version of the preset format written by this version of Gradience
+pub const PRESET_SCHEMA: u32 = 3;
+
+/// Changes made to a preset document to bring it to [`PRESET_SCHEMA`]
+#[derive(Debug, Clone)]
+pub struct MigrationReport {
+    pub from: u32,
+    pub to: u32,
+    pub changes: Vec<String>,
+}
+
+impl MigrationReport {
+    pub fn is_empty(&self) -> bool {
+        self.from == self.to && self.changes.is_empty()
+    }
+}
+
+/// A step upgrading a document from the schema at its index + 1 to the next one
+type Migration = fn(&mut Map<String, Value>, &mut Vec<String>);
+
+const MIGRATIONS: [Migration; 2] = [v1_to_v2, v2_to_v3];
+
+/// License names used before licenses were SPDX expressions and their SPDX identifier
+const LICENSE_NAMES: [(&str, &str); 14] = [
+    ("GPL3", "GPL-3.0-only"),
+    ("MIT", "MIT"),
+    ("Apache2", "Apache-2.0"),
+    ("BSD", "BSD-3-Clause"),
+    ("LGPL3", "LGPL-3.0-only"),
+    ("AGPL3", "AGPL-3.0-only"),
+    ("MPL2", "MPL-2.0"),
+    ("CC0", "CC0-1.0"),
+    ("CCBY", "CC-BY-4.0"),
+    ("CCBYSA", "CC-BY-SA-4.0"),
+    ("CCBYNC", "CC-BY-NC-4.0"),
+    ("CCBYNCSA", "CC-BY-NC-SA-4.0"),
+    ("CCBYND", "CC-BY-ND-4.0"),
+    ("CCBYNCND", "CC-BY-NC-ND-4.0"),
+];
+
+/// Returns the SPDX identifier of a license name used before licenses were SPDX
+/// expressions, spaces are ignored so `GPL 3` is read as `GPL3`
+pub(crate) fn license_alias(name: &str) -> Option<&'static str> {
+    let name: String = name.split_whitespace().collect();
+    LICENSE_NAMES
+        .iter()
+        .find(|(old, id)| *old == name && old != id)
+        .map(|(_, id)| *id)
+}
+
+/// Presets without `schema` predate it: palette hues could end with `_` and the custom
+/// CSS was named `custom_css`
+fn v1_to_v2(preset: &mut Map<String, Value>, changes: &mut Vec<String>) {
+    rename(preset, "custom_css", "custom", "", changes);
+
+    if let Some(palette) = preset.get_mut("palette").and_then(|p| p.as_object_mut()) {
+        let hues: Vec<String> = palette
+            .keys()
+            .filter(|hue| hue.ends_with('_'))
+            .cloned()
+            .collect();
+        for hue in hues {
+            rename(palette, &hue, hue.trim_end_matches('_'), "palette.", changes);
+        }
+    }
+}
+
+/// Licenses are SPDX expressions since the third schema
+fn v2_to_v3(preset: &mut Map<String, Value>, changes: &mut Vec<String>) {
+    let Some(Value::String(license)) = preset.get_mut("license") else {
+        return;
+    };
+    let name = license.trim().to_string();
+    if let Some(id) = license_alias(&name) {
+        changes.push(format!("changed license {} to {}", name, id));
+        *license = id.to_string();
+    }
+}
+
+fn rename(
+    object: &mut Map<String, Value>,
+    from: &str,
+    to: &str,
+    parent: &str,
+    changes: &mut Vec<String>,
+) {
+    if object.contains_key(to) {
+        return;
+    }
+    if let Some(index) = object.keys().position(|key| key == from) {
+        let value = object.shift_remove(from).unwrap();
+        object.shift_insert(index, to.to_string(), value);
+        changes.push(format!("renamed {}{} to {}{}", parent, from, parent, to));
+    }
+}
+
+/// Upgrades a preset document in place, one schema at a time, documents without
+/// `schema` are considered to be the first version of the format
+pub fn migrate(preset: &mut Value) -> Result<MigrationReport, std::io::Error> {
+    let invalid = |message: String| std::io::Error::new(std::io::ErrorKind::InvalidData, message);
+    let preset = preset
+        .as_object_mut()
+        .ok_or(invalid("A preset must be a JSON object".to_string()))?;
+
+    let from = match preset.get("schema") {
+        None => 1,
+        Some(schema) => {
+            let number = schema
+                .as_u64()
+                .filter(|schema| *schema >= 1)
+                .ok_or(invalid(format!("Invalid preset schema {}", schema)))?;
+            u32::try_from(number)
+                .map_err(|_| invalid(format!("Unsupported preset schema {}", schema)))?
+        }
+    };
+    if from > PRESET_SCHEMA {
+        return Err(invalid(format!(
+            "The preset schema {} is newer than the supported one ({}), update Gradience",
+            from, PRESET_SCHEMA
+        )));
+    }
+
+    let mut changes = Vec::new();
+    for migration in &MIGRATIONS[from as usize - 1..] {
+        migration(preset, &mut changes);
+    }
+    preset.shift_remove("schema");
+    preset.shift_insert(0, "schema".to_string(), Value::from(PRESET_SCHEMA));
+
+    Ok(MigrationReport {
+        from,
+        to: PRESET_SCHEMA,
+        changes,
+    })
+}
@@ -1,5 +1,8 @@
-use handlebars::Handlebars;
-use serde::{Deserialize, Serialize};
+use crate::helpers::template_registry;
+use crate::migration::{license_alias, migrate, MigrationReport, PRESET_SCHEMA};
+use crate::tokens::Tokens;
+use crate::utils::slugify;
+use serde::{Deserialize, Serialize, Serializer};
 use std::collections::BTreeMap;
 use std::collections::HashMap;
 
@@ -16,25 +19,76 @@ pub enum AccentsColor {
     Slate,
 }
 
+impl AccentsColor {
+    pub const ALL: [AccentsColor; 9] = [
+        AccentsColor::Blue,
+        AccentsColor::Teal,
+        AccentsColor::Green,
+        AccentsColor::Yellow,
+        AccentsColor::Orange,
+        AccentsColor::Red,
+        AccentsColor::Pink,
+        AccentsColor::Purple,
+        AccentsColor::Slate,
+    ];
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum Mode {
     Light,
     Dark,
 }
 
+impl Mode {
+    pub const ALL: [Mode; 2] = [Mode::Light, Mode::Dark];
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum GtkVersion {
     Gtk3,
     Gtk4,
 }
 
+/// Orders numeric keys like palette shades by value, before the other keys
+fn compare_keys(a: &str, b: &str) -> std::cmp::Ordering {
+    match (a.parse::<u32>(), b.parse::<u32>()) {
+        (Ok(a), Ok(b)) => a.cmp(&b),
+        (Ok(_), Err(_)) => std::cmp::Ordering::Less,
+        (Err(_), Ok(_)) => std::cmp::Ordering::Greater,
+        (Err(_), Err(_)) => a.cmp(b),
+    }
+}
+
+/// Serializes a map with sorted keys, so saved presets don't change between runs
+fn serialize_sorted<S: Serializer>(
+    map: &HashMap<String, String>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let mut entries: Vec<(&String, &String)> = map.iter().collect();
+    entries.sort_by(|(a, _), (b, _)| compare_keys(a, b));
+    serializer.collect_map(entries)
+}
+
+fn default_schema() -> u32 {
+    PRESET_SCHEMA
+}
+
 fn default_version() -> String {
     "0.0.1".to_string()
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Preset {
+    /// Version of the preset format, older presets are migrated when they are loaded
+    #[serde(default = "default_schema")]
+    #[cfg_attr(feature = "schema", schemars(range(min = 1)))]
+    pub schema: u32,
     pub name: String,
+    /// Identifier of the preset in stores, defaults to the slug of the name. Setting it
+    /// keeps the identifier when the preset is renamed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
 
     #[serde(default = "default_version")]
     pub version: String,
@@ -49,13 +103,15 @@ pub struct Preset {
     pub license: License,
     pub variables: Variables,
     pub palette: Palette,
-    #[serde(alias = "custom_css")]
     pub custom: Custom,
     #[serde(default)]
     pub shell: Shell,
+    #[serde(default)]
+    pub tokens: Tokens,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Shell {
     #[serde(default = "Shell::default_bg_color")]
     pub bg_color: Variable,
@@ -169,12 +225,13 @@ impl Shell {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Author {
-    name: String,
+    pub name: String,
     #[serde(default)]
-    email: String,
+    pub email: String,
     #[serde(default)]
-    url: String,
+    pub url: String,
 }
 
 impl Default for Author {
@@ -188,12 +245,13 @@ impl Default for Author {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Supported {
-    gnome: String,
-    adw: String,
-    gtk: String,
-    mode: Vec<String>,
-    accents: Vec<String>,
+    pub gnome: String,
+    pub adw: String,
+    pub gtk: String,
+    pub mode: Vec<String>,
+    pub accents: Vec<String>,
 }
 
 impl Default for Supported {
@@ -218,31 +276,76 @@ impl Default for Supported {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub enum License {
-    GPL3,
-    MIT,
-    Apache2,
-    BSD,
-    LGPL3,
-    AGPL3,
-    MPL2,
-    CC0,
-    CCBY,
-    CCBYSA,
-    CCBYNC,
-    CCBYNCSA,
-    CCBYND,
-    CCBYNCND,
+/// An SPDX license identifier or expression, e.g. `MIT`, `CC-BY-SA-4.0` or
+/// `MIT OR Apache-2.0`. The names used by older presets, like `CCBYSA`, are aliases of
+/// their SPDX identifier
+#[derive(Debug, Clone, PartialEq)]
+pub struct License(String);
+
+impl License {
+    /// Checks that `expression` is a valid SPDX expression, `LicenseRef-` identifiers are
+    /// allowed for other licenses and old license names are replaced by their identifier
+    pub fn parse(expression: &str) -> Result<License, std::io::Error> {
+        let expression = license_alias(expression).unwrap_or(expression.trim());
+        spdx::Expression::parse(expression).map_err(|err| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "Invalid SPDX license expression {}: {}",
+                    expression, err.reason
+                ),
+            )
+        })?;
+        Ok(License(expression.to_string()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
 }
 
 impl Default for License {
     fn default() -> Self {
-        License::GPL3
+        License("GPL-3.0-only".to_string())
+    }
+}
+
+impl std::fmt::Display for License {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Serialize for License {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for License {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<License, D::Error> {
+        License::parse(&String::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(feature = "schema")]
+impl schemars::JsonSchema for License {
+    fn schema_name() -> String {
+        "License".to_string()
+    }
+
+    fn json_schema(_: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        serde_json::from_value(serde_json::json!({
+            "description": "An SPDX license identifier or expression",
+            "type": "string",
+            "examples": ["MIT", "GPL-3.0-or-later", "CC-BY-SA-4.0", "MIT OR Apache-2.0"]
+        }))
+        .unwrap()
     }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Variables {
     #[serde(default = "Variables::default_accent_color")]
     pub accent_color: Variable,
@@ -567,7 +670,7 @@ impl Variables {
         }
     }
 
-    pub fn iter(&self) -> VariablesIterator {
+    pub fn iter(&self) -> VariablesIterator<'_> {
         VariablesIterator {
             variables: self,
             name: VariablesName::AccentColor,
@@ -891,13 +994,17 @@ impl Variables {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(untagged)]
 pub enum Variable {
+    /// The same color everywhere
     Single(String),
+    /// A color for each mode
     Mode {
         light: String,
         dark: String,
     },
+    /// A color for each accent, `default` is used for missing accents
     Accent {
         #[serde(default)]
         blue: String,
@@ -919,8 +1026,11 @@ pub enum Variable {
         teal: String,
         default: String,
     },
+    /// A color for each mode and accent, the `default` key is used for missing accents
     ModeAccent {
+        #[serde(serialize_with = "serialize_sorted")]
         light: HashMap<String, String>,
+        #[serde(serialize_with = "serialize_sorted")]
         dark: HashMap<String, String>,
     },
 }
@@ -1033,28 +1143,80 @@ impl Variable {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Palette {
-    #[serde(alias = "blue_")]
+    #[serde(default, serialize_with = "serialize_sorted")]
     pub blue: HashMap<String, String>,
-    #[serde(alias = "green_")]
+    #[serde(default, serialize_with = "serialize_sorted")]
+    pub teal: HashMap<String, String>,
+    #[serde(default, serialize_with = "serialize_sorted")]
     pub green: HashMap<String, String>,
-    #[serde(alias = "yellow_")]
+    #[serde(default, serialize_with = "serialize_sorted")]
     pub yellow: HashMap<String, String>,
-    #[serde(alias = "orange_")]
+    #[serde(default, serialize_with = "serialize_sorted")]
     pub orange: HashMap<String, String>,
-    #[serde(alias = "red_")]
+    #[serde(default, serialize_with = "serialize_sorted")]
     pub red: HashMap<String, String>,
-    #[serde(alias = "purple_")]
+    #[serde(default, serialize_with = "serialize_sorted")]
+    pub pink: HashMap<String, String>,
+    #[serde(default, serialize_with = "serialize_sorted")]
     pub purple: HashMap<String, String>,
-    #[serde(alias = "brown_")]
+    #[serde(default, serialize_with = "serialize_sorted")]
     pub brown: HashMap<String, String>,
-    #[serde(alias = "light_")]
+    #[serde(default, serialize_with = "serialize_sorted")]
+    pub slate: HashMap<String, String>,
+    #[serde(default, serialize_with = "serialize_sorted")]
     pub light: HashMap<String, String>,
-    #[serde(alias = "dark_")]
+    #[serde(default, serialize_with = "serialize_sorted")]
     pub dark: HashMap<String, String>,
 }
 
+impl Palette {
+    /// Hues of the palette, in the order of the Adwaita palette and accents
+    pub const HUES: [&'static str; 12] = [
+        "blue", "teal", "green", "yellow", "orange", "red", "pink", "purple", "brown", "slate",
+        "light", "dark",
+    ];
+
+    /// Returns the shades of a hue, `None` if the hue doesn't exist
+    pub fn hue(&self, hue: &str) -> Option<&HashMap<String, String>> {
+        match hue {
+            "blue" => Some(&self.blue),
+            "teal" => Some(&self.teal),
+            "green" => Some(&self.green),
+            "yellow" => Some(&self.yellow),
+            "orange" => Some(&self.orange),
+            "red" => Some(&self.red),
+            "pink" => Some(&self.pink),
+            "purple" => Some(&self.purple),
+            "brown" => Some(&self.brown),
+            "slate" => Some(&self.slate),
+            "light" => Some(&self.light),
+            "dark" => Some(&self.dark),
+            _ => None,
+        }
+    }
+
+    /// Returns a color of the palette, e.g. `get("blue", "3")`
+    pub fn get(&self, hue: &str, shade: &str) -> Option<&str> {
+        self.hue(hue)?.get(shade).map(|color| color.as_str())
+    }
+
+    /// Iterates over `(hue, shade, color)`, hues in [`Palette::HUES`] order and shades
+    /// sorted numerically
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, &str, &str)> {
+        Palette::HUES.into_iter().flat_map(move |hue| {
+            let mut shades: Vec<(&String, &String)> = self.hue(hue).unwrap().iter().collect();
+            shades.sort_by(|(a, _), (b, _)| compare_keys(a, b));
+            shades
+                .into_iter()
+                .map(move |(shade, color)| (hue, shade.as_str(), color.as_str()))
+        })
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Custom {
     #[serde(default)]
     pub gtk4: String,
@@ -1062,16 +1224,100 @@ pub struct Custom {
     pub gtk3: String,
     #[serde(default)]
     pub shell: String,
+    /// SCSS appended to a shell widget partial, keyed by its name (`panel`, `dash`,
+    /// `quick-settings`...), so it can use the variables and mixins of the partial
+    #[serde(default)]
+    pub shell_widgets: BTreeMap<String, String>,
+    /// Values replacing the definition of shell SCSS variables, e.g. `base_border_radius`
+    /// or `panel_height`
+    #[serde(default)]
+    pub shell_variables: BTreeMap<String, String>,
 }
 
 impl Preset {
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Preset {
-        let p: Preset = serde_json::from_str(s).unwrap();
-        p
+        Preset::from_str_migrated(s).unwrap().0
     }
 
+    /// Parses a preset written with any version of the format, and returns the changes
+    /// made to bring it to the current one
+    pub fn from_str_migrated(s: &str) -> Result<(Preset, MigrationReport), std::io::Error> {
+        let mut value: serde_json::Value = serde_json::from_str(s)?;
+        let report = migrate(&mut value)?;
+        let preset = serde_json::from_value(value)?;
+        Ok((preset, report))
+    }
+
+    /// Returns the JSON Schema of the preset format, for editors to validate and complete
+    /// preset files
+    #[cfg(feature = "schema")]
+    pub fn json_schema() -> String {
+        let mut schema = schemars::schema_for!(Preset);
+        schema.schema.metadata().title = Some("Gradience preset".to_string());
+        serde_json::to_string_pretty(&schema).unwrap()
+    }
+
+    /// Returns the preset as pretty-printed JSON, keys follow the order of the format
     pub fn to_str(&self) -> String {
-        serde_json::to_string(self).unwrap()
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    /// Like [`Preset::to_str`] but without the values equal to their default, e.g. the
+    /// shell colors or variables the preset doesn't change
+    pub fn to_str_without_defaults(&self) -> String {
+        // sections removed when they are equal to their default
+        let defaults = serde_json::json!({
+            "version": default_version(),
+            "author": Author::default(),
+            "description": "",
+            "supported": Supported::default(),
+            "license": License::default(),
+            "shell": Shell::default(),
+            "tokens": Tokens::default(),
+        });
+        // sections whose fields are removed when they are equal to their default
+        let field_defaults = serde_json::json!({
+            "author": { "email": "", "url": "" },
+            "variables": serde_json::from_str::<Variables>("{}").unwrap(),
+            "palette": {},
+            "custom": serde_json::from_str::<Custom>("{}").unwrap(),
+            "shell": Shell::default(),
+        });
+
+        let mut value = serde_json::to_value(self).unwrap();
+        let preset = value.as_object_mut().unwrap();
+        for (key, default) in defaults.as_object().unwrap() {
+            if preset.get(key) == Some(default) {
+                preset.shift_remove(key);
+            }
+        }
+
+        for (key, defaults) in field_defaults.as_object().unwrap() {
+            let Some(section) = preset.get_mut(key).and_then(|s| s.as_object_mut()) else {
+                continue;
+            };
+            section.retain(|name, value| {
+                // accents of a variable and palette hues are empty by default
+                if let Some(object) = value.as_object_mut() {
+                    if object.contains_key("default") {
+                        object.retain(|_, color| color != "");
+                    }
+                    if object.is_empty() {
+                        return false;
+                    }
+                }
+                defaults.get(name) != Some(value)
+            });
+        }
+
+        serde_json::to_string_pretty(&value).unwrap()
+    }
+
+    /// Returns the identifier of the preset, it's filesystem-safe so it's also the name
+    /// of the file of the preset in stores
+    pub fn id(&self) -> String {
+        slugify(self.id.as_deref().unwrap_or(&self.name))
     }
 
     pub fn from_file(file: &str) -> Preset {
@@ -1079,13 +1325,20 @@ impl Preset {
         Preset::from_str(&s)
     }
 
+    /// Like [`Preset::from_str_migrated`] but reads the preset from a file
+    pub fn from_file_migrated(file: &str) -> Result<(Preset, MigrationReport), std::io::Error> {
+        Preset::from_str_migrated(&std::fs::read_to_string(file)?)
+    }
+
     pub fn to_file(&self, file: &str) {
         let s = self.to_str();
-        std::fs::write(file, s).unwrap();
+        std::fs::write(file, s + "\n").unwrap();
     }
 
     pub fn to_css(&self, mode: Mode, accent: AccentsColor, gtk: GtkVersion) -> String {
-        let mut css = match gtk {
+        // custom CSS comes after the tokens so it can override them
+        let mut css = self.tokens.to_css();
+        css += &match gtk {
             GtkVersion::Gtk3 => self.custom.gtk3.clone(),
             GtkVersion::Gtk4 => self.custom.gtk4.clone(),
         };
@@ -1117,40 +1370,8 @@ impl Preset {
             css += &format!("@define-color {} {};\n", name.to_slug(), value);
         }
 
-        for (name, value) in self.palette.blue.iter() {
-            css += &format!("@define-color blue_{} {};\n", name, value);
-        }
-
-        for (name, value) in self.palette.green.iter() {
-            css += &format!("@define-color green_{} {};\n", name, value);
-        }
-
-        for (name, value) in self.palette.yellow.iter() {
-            css += &format!("@define-color yellow_{} {};\n", name, value);
-        }
-
-        for (name, value) in self.palette.orange.iter() {
-            css += &format!("@define-color orange_{} {};\n", name, value);
-        }
-
-        for (name, value) in self.palette.red.iter() {
-            css += &format!("@define-color red_{} {};\n", name, value);
-        }
-
-        for (name, value) in self.palette.purple.iter() {
-            css += &format!("@define-color purple_{} {};\n", name, value);
-        }
-
-        for (name, value) in self.palette.brown.iter() {
-            css += &format!("@define-color brown_{} {};\n", name, value);
-        }
-
-        for (name, value) in self.palette.light.iter() {
-            css += &format!("@define-color light_{} {};\n", name, value);
-        }
-
-        for (name, value) in self.palette.dark.iter() {
-            css += &format!("@define-color dark_{} {};\n", name, value);
+        for (hue, shade, color) in self.palette.iter() {
+            css += &format!("@define-color {}_{} {};\n", hue, shade, color);
         }
 
         css = css.replace("@mode", mode);
@@ -1158,9 +1379,45 @@ impl Preset {
         css
     }
 
-    pub fn render_template(&self, template: String, mode: Mode, accent: AccentsColor) -> String {
-        let reg = Handlebars::new();
+    /// Returns the `index.theme` describing a theme built from this preset
+    pub fn to_index_theme(&self, theme_name: &str, mode: Mode, accent: AccentsColor) -> String {
+        // values of a desktop entry are single line
+        let escape = |value: &str| value.replace('\\', "\\\\").replace('\n', "\\n");
 
+        let comment = if self.description.is_empty() {
+            format!("{} theme generated with Gradience", self.name)
+        } else {
+            self.description.clone()
+        };
+        let author = if self.author.email.is_empty() {
+            self.author.name.clone()
+        } else {
+            format!("{} <{}>", self.author.name, self.author.email)
+        };
+
+        let mut index = String::new();
+        index += "[Desktop Entry]\n";
+        index += "Type=X-GNOME-Metatheme\n";
+        index += &format!("Name={}\n", escape(theme_name));
+        index += &format!("Comment={}\n", escape(&comment));
+        index += "Encoding=UTF-8\n";
+        index += &format!("X-Gradience-Preset={}\n", escape(&self.name));
+        index += &format!("X-Gradience-Version={}\n", escape(&self.version));
+        index += &format!("X-Gradience-Author={}\n", escape(&author));
+        if !self.author.url.is_empty() {
+            index += &format!("X-Gradience-Url={}\n", escape(&self.author.url));
+        }
+        index += &format!("X-Gradience-License={}\n", escape(self.license.as_str()));
+        index += &format!("X-Gradience-Mode={:?}\n", mode);
+        index += &format!("X-Gradience-Accent={:?}\n", accent);
+        index += "\n";
+        index += "[X-GNOME-Metatheme]\n";
+        index += &format!("GtkTheme={}\n", escape(theme_name));
+        index
+    }
+
+    /// Returns the data used to render shell templates for the given mode and accent
+    pub fn template_data(&self, mode: Mode, accent: AccentsColor) -> BTreeMap<String, String> {
         let mode = match mode {
             Mode::Light => "light",
             Mode::Dark => "dark",
@@ -1229,54 +1486,34 @@ impl Preset {
         }
 
         // insert all palette colors
-        for (name, value) in self.palette.blue.iter() {
-            data.insert(format!("blue_{}", name), value.to_string());
-        }
-
-        for (name, value) in self.palette.green.iter() {
-            data.insert(format!("green_{}", name), value.to_string());
-        }
-
-        for (name, value) in self.palette.yellow.iter() {
-            data.insert(format!("yellow_{}", name), value.to_string());
-        }
-
-        for (name, value) in self.palette.orange.iter() {
-            data.insert(format!("orange_{}", name), value.to_string());
-        }
-
-        for (name, value) in self.palette.red.iter() {
-            data.insert(format!("red_{}", name), value.to_string());
-        }
-
-        for (name, value) in self.palette.purple.iter() {
-            data.insert(format!("purple_{}", name), value.to_string());
-        }
-
-        for (name, value) in self.palette.brown.iter() {
-            data.insert(format!("brown_{}", name), value.to_string());
-        }
-
-        for (name, value) in self.palette.light.iter() {
-            data.insert(format!("light_{}", name), value.to_string());
-        }
-
-        for (name, value) in self.palette.dark.iter() {
-            data.insert(format!("dark_{}", name), value.to_string());
+        for (hue, shade, color) in self.palette.iter() {
+            data.insert(format!("{}_{}", hue, shade), color.to_string());
         }
 
+        let tokens = self.tokens.template_data(&data);
+        data.extend(tokens);
         data.insert("custom_css".to_string(), self.custom.shell.clone());
         data.insert("mode".to_string(), mode.to_string());
         data.insert("accent".to_string(), accent.to_string());
+        data
+    }
 
-        let mut result = reg.render_template(&template, &data).unwrap();
+    pub fn render_template(&self, template: String, mode: Mode, accent: AccentsColor) -> String {
+        let reg = template_registry();
+        let data = self.template_data(mode, accent);
 
-        result = result.replace("@mode", mode);
-        result = result.replace("@accent", &accent.to_string());
-        result
+        let result = reg.render_template(&template, &data).unwrap();
+        replace_variant(result, &data)
     }
 }
 
+/// Replaces the `@mode` and `@accent` placeholders left after rendering a template
+pub(crate) fn replace_variant(rendered: String, data: &BTreeMap<String, String>) -> String {
+    rendered
+        .replace("@mode", &data["mode"])
+        .replace("@accent", &data["accent"])
+}
+
 pub struct ApplyBuilder {
     preset: Preset,
     gtk3_path: String,
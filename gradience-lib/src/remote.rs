@@ -0,0 +1,322 @@
+use crate::preset::Preset;
+#[cfg(feature = "online")]
+use reqwest::header::{HeaderMap, HeaderValue, USER_AGENT};
+use serde::{Deserialize, Serialize};
+use std::io::{Error, ErrorKind};
+use walkdir::WalkDir;
+
+const COMMUNITY_NAME: &str = "community";
+pub(crate) const COMMUNITY_URL: &str = "https://github.com/t-dantiau/Community";
+
+/// JSON files found in repositories which aren't presets
+const NOT_PRESETS: [&str; 6] = [
+    "index.json",
+    "package.json",
+    "package-lock.json",
+    "tsconfig.json",
+    "renovate.json",
+    "composer.json",
+];
+
+/// A source of presets to download. The URL is either a GitHub repository, like
+/// `https://github.com/owner/repo` or `https://github.com/owner/repo/tree/branch`, a
+/// `file://` directory, or the URL of an index which is a JSON list of preset files
+/// relative to it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Remote {
+    pub name: String,
+    pub url: String,
+    /// Remotes with a higher priority are listed and searched first
+    #[serde(default)]
+    pub priority: i32,
+}
+
+/// A preset available on a remote, `name` is its path in the remote without `.json`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OnlinePreset {
+    pub remote: String,
+    pub name: String,
+    pub url: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct GhApiUrlResponse {
+    pub tree: Vec<Tree>,
+    pub url: String,
+    pub sha: String,
+    pub truncated: bool,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Tree {
+    pub path: String,
+    pub mode: String,
+
+    #[serde(rename(deserialize = "type"))]
+    pub type_: String,
+    pub sha: String,
+    #[serde(default)]
+    pub size: u32,
+    pub url: String,
+}
+
+enum Source {
+    GitHub {
+        owner: String,
+        repo: String,
+        branch: String,
+    },
+    Directory(String),
+    Index(String),
+}
+
+impl Remote {
+    pub fn new(name: &str, url: &str) -> Result<Remote, Error> {
+        if name.is_empty() || name.contains([':', '/']) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("invalid remote name {}, it can't contain : or /", name),
+            ));
+        }
+        let remote = Remote {
+            name: name.to_string(),
+            url: url.trim_end_matches('/').to_string(),
+            priority: 0,
+        };
+        remote.source()?;
+        Ok(remote)
+    }
+
+    pub fn priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// The community presets repository, the only remote until others are added
+    pub fn community() -> Remote {
+        Remote::new(COMMUNITY_NAME, COMMUNITY_URL).unwrap()
+    }
+
+    fn source(&self) -> Result<Source, Error> {
+        if let Some(path) = self.url.strip_prefix("file://") {
+            return Ok(Source::Directory(path.to_string()));
+        }
+        if let Some(path) = self.url.strip_prefix("https://github.com/") {
+            let parts: Vec<&str> = path.split('/').collect();
+            return match parts.as_slice() {
+                [owner, repo] => Ok(Source::GitHub {
+                    owner: owner.to_string(),
+                    repo: repo.to_string(),
+                    branch: "main".to_string(),
+                }),
+                [owner, repo, "tree", branch] => Ok(Source::GitHub {
+                    owner: owner.to_string(),
+                    repo: repo.to_string(),
+                    branch: branch.to_string(),
+                }),
+                _ => Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("{} isn't a GitHub repository URL", self.url),
+                )),
+            };
+        }
+        if self.url.starts_with("https://") || self.url.starts_with("http://") {
+            return Ok(Source::Index(self.url.clone()));
+        }
+        Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "unsupported remote URL {}, use http(s):// or file://",
+                self.url
+            ),
+        ))
+    }
+
+    /// Returns the URL of a preset of the remote
+    fn preset_url(&self, name: &str) -> Result<String, Error> {
+        if !is_valid_name(name) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("invalid preset name {}", name),
+            ));
+        }
+        Ok(match self.source()? {
+            Source::GitHub {
+                owner,
+                repo,
+                branch,
+            } => format!(
+                "https://github.com/{}/{}/raw/{}/{}.json",
+                owner, repo, branch, name
+            ),
+            Source::Directory(dir) => format!("file://{}/{}.json", dir, name),
+            Source::Index(index) => format!("{}/{}.json", parent_url(&index), name),
+        })
+    }
+
+    /// Lists the presets of the remote
+    pub fn list(&self) -> Result<Vec<OnlinePreset>, Error> {
+        let names: Vec<String> = match self.source()? {
+            Source::GitHub {
+                owner,
+                repo,
+                branch,
+            } => {
+                let url = format!(
+                    "https://api.github.com/repos/{}/{}/git/trees/{}?recursive=1",
+                    owner, repo, branch
+                );
+                let response: GhApiUrlResponse = serde_json::from_str(&fetch(&url)?)?;
+                if response.truncated {
+                    return Err(Error::other(format!(
+                        "{}/{} has too many files to be listed by GitHub, use an index instead",
+                        owner, repo
+                    )));
+                }
+                response
+                    .tree
+                    .into_iter()
+                    .filter(|tree| tree.type_ == "blob")
+                    .filter_map(|tree| preset_name(&tree.path))
+                    .collect()
+            }
+            Source::Directory(dir) => WalkDir::new(&dir)
+                .sort_by_file_name()
+                .into_iter()
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.file_type().is_file())
+                .filter_map(|entry| preset_name(entry.path().strip_prefix(&dir).ok()?.to_str()?))
+                .collect(),
+            Source::Index(index) => {
+                let files: Vec<String> = serde_json::from_str(&fetch(&index)?)?;
+                files.iter().filter_map(|file| preset_name(file)).collect()
+            }
+        };
+
+        names
+            .into_iter()
+            .map(|name| {
+                Ok(OnlinePreset {
+                    remote: self.name.clone(),
+                    url: self.preset_url(&name)?,
+                    name,
+                })
+            })
+            .collect()
+    }
+
+    /// Downloads a preset of the remote, `name` is the name given by [`Remote::list`]
+    pub fn download(&self, name: &str) -> Result<Preset, Error> {
+        let (preset, _) = Preset::from_str_migrated(&fetch(&self.preset_url(name)?)?)?;
+        Ok(preset)
+    }
+}
+
+/// Returns the remotes sorted by priority, the first one added first for equal priorities
+pub fn by_priority(remotes: &[Remote]) -> Vec<Remote> {
+    let mut remotes = remotes.to_vec();
+    remotes.sort_by_key(|remote| std::cmp::Reverse(remote.priority));
+    remotes
+}
+
+/// Downloads a preset from the first remote which has it. `name` can be prefixed with the
+/// name of a remote, like `community:nord`, to only look in this remote
+pub fn download(remotes: &[Remote], name: &str) -> Result<Preset, Error> {
+    let (remotes, name): (Vec<Remote>, &str) = match name.split_once(':') {
+        Some((remote, name)) => (
+            remotes
+                .iter()
+                .filter(|r| r.name == remote)
+                .cloned()
+                .collect(),
+            name,
+        ),
+        None => (by_priority(remotes), name),
+    };
+    if remotes.is_empty() {
+        return Err(Error::new(ErrorKind::NotFound, "no matching remote"));
+    }
+
+    for remote in &remotes {
+        match remote.download(name) {
+            Err(error) if error.kind() == ErrorKind::NotFound => continue,
+            result => return result,
+        }
+    }
+    Err(Error::new(
+        ErrorKind::NotFound,
+        format!("no remote has a preset named {}", name),
+    ))
+}
+
+/// Reads the remotes saved in a file, when the file doesn't exist the community
+/// repository is the only remote
+pub fn load_remotes(path: &str) -> Result<Vec<Remote>, Error> {
+    match std::fs::read_to_string(path) {
+        Ok(content) => Ok(serde_json::from_str(&content)?),
+        Err(error) if error.kind() == ErrorKind::NotFound => Ok(vec![Remote::community()]),
+        Err(error) => Err(error),
+    }
+}
+
+pub fn save_remotes(path: &str, remotes: &[Remote]) -> Result<(), Error> {
+    if let Some(parent) = std::path::Path::new(path).parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, serde_json::to_string_pretty(remotes)? + "\n")
+}
+
+/// Returns the name of a preset from its path in a remote, or `None` when the file isn't
+/// a preset or its path leaves the remote
+fn preset_name(path: &str) -> Option<String> {
+    let name = path.strip_suffix(".json")?;
+    let file = path.rsplit('/').next()?;
+    let hidden = name.split('/').any(|part| part.starts_with('.'));
+    (is_valid_name(name) && !hidden && !NOT_PRESETS.contains(&file)).then(|| name.to_string())
+}
+
+/// Names are relative paths inside the remote, without `.` or `..` components
+fn is_valid_name(name: &str) -> bool {
+    !name.contains('\\') && name.split('/').all(|part| !matches!(part, "" | "." | ".."))
+}
+
+fn parent_url(url: &str) -> &str {
+    url.rsplit_once('/').map_or(url, |(parent, _)| parent)
+}
+
+fn fetch(url: &str) -> Result<String, Error> {
+    if let Some(path) = url.strip_prefix("file://") {
+        return std::fs::read_to_string(path);
+    }
+    fetch_http(url)
+}
+
+#[cfg(feature = "online")]
+fn fetch_http(url: &str) -> Result<String, Error> {
+    let mut headers = HeaderMap::new();
+    headers.insert(USER_AGENT, HeaderValue::from_static("reqwest"));
+
+    let response = reqwest::blocking::Client::new()
+        .get(url)
+        .headers(headers)
+        .send()
+        .map_err(Error::other)?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Err(Error::new(
+            ErrorKind::NotFound,
+            format!("{} not found", url),
+        ));
+    }
+    response
+        .error_for_status()
+        .and_then(|response| response.text())
+        .map_err(Error::other)
+}
+
+#[cfg(not(feature = "online"))]
+fn fetch_http(url: &str) -> Result<String, Error> {
+    Err(Error::new(
+        ErrorKind::Unsupported,
+        format!("can't download {}, the online feature is disabled", url),
+    ))
+}
@@ -1,12 +1,114 @@
-use crate::preset::{AccentsColor, ApplyBuilder, Mode, Preset};
-use crate::utils::{get_gnome_shell_version, run_command, ShellVersion, set_shell_theme};
+use crate::gresource::GResourceBuilder;
+use crate::helpers::template_registry;
+use crate::preset::{replace_variant, AccentsColor, ApplyBuilder, Mode, Preset};
+use crate::utils::{get_gnome_shell_version, set_shell_theme, ShellVersion};
 use grass::from_path;
+use handlebars::{Handlebars, RenderError, RenderErrorReason};
+use std::collections::{BTreeMap, BTreeSet};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 use walkdir::WalkDir;
 
+/// Files and directories making a theme in `themes_dir`
+pub const THEME_LAYOUT: [&str; 4] = ["gnome-shell", "gtk-3.0", "gtk-4.0", "index.theme"];
+
+/// Adwaita accent colors hard-coded in upstream assets and the variables replacing them,
+/// only `gnome-shell-start.svg` has some, the other assets are black and white
+const SVG_ACCENT_COLORS: [(&str, &str); 2] = [
+    ("#3580e4", "accent_bg_color"),
+    ("#8abfdd", "accent_color"),
+];
+
+/// Prefix of the assets bundled with GNOME Shell, used by upstream stylesheets
+const SHELL_RESOURCE_PREFIX: &str = "resource:///org/gnome/shell/theme/";
+
+/// Path of the shell theme inside `gnome-shell-theme.gresource`
+const SHELL_RESOURCE_PATH: &str = "/org/gnome/shell/theme/";
+
+/// Names GNOME Shell loads its stylesheet from, depending on the session
+const SHELL_STYLESHEETS: [&str; 3] = ["gnome-shell.css", "gnome-shell-dark.css", "gnome-shell-light.css"];
+
+/// Directory of the widget partials, relative to the shell source
+const SHELL_WIDGETS_DIR: &str = "gnome-shell-sass/widgets";
+
+/// Replaces the Adwaita accent colors of an SVG with the preset ones
+fn recolor_svg(svg: &str, data: &BTreeMap<String, String>) -> String {
+    let mut svg = svg.to_string();
+    for (color, variable) in SVG_ACCENT_COLORS {
+        svg = svg
+            .replace(color, &data[variable])
+            .replace(&color.to_uppercase(), &data[variable]);
+    }
+    svg
+}
+
+/// Replaces the top level definitions of the overridden variables, the definition stays
+/// at the same place so values derived from it use the override too
+fn override_variables(
+    scss: &str,
+    variables: &BTreeMap<String, String>,
+    overridden: &mut BTreeSet<String>,
+) -> String {
+    let mut output = String::with_capacity(scss.len());
+    for line in scss.split_inclusive('\n') {
+        let name = line
+            .strip_prefix('$')
+            .and_then(|rest| rest.split_once(':'))
+            .map(|(name, _)| name.trim());
+        match name.and_then(|name| variables.get_key_value(name)) {
+            Some((name, value)) => {
+                output += &format!("${}: {};\n", name, value);
+                overridden.insert(name.to_string());
+            }
+            None => output += line,
+        }
+    }
+    output
+}
+
+/// Returns the path a rendered template is written to, `_colors.template` becomes
+/// `_colors.scss` while `toggle-on.svg.template` becomes `toggle-on.svg`
+fn rendered_path(template: &std::path::Path) -> std::path::PathBuf {
+    let stem = template.with_extension("");
+    if stem.extension().is_some() {
+        stem
+    } else {
+        template.with_extension("scss")
+    }
+}
+
+fn version_dir(version: ShellVersion) -> Result<&'static str, std::io::Error> {
+    match version {
+        ShellVersion::G46 => Ok("46"),
+        ShellVersion::Unsupported => Err(std::io::Error::other("Unsupported shell version")),
+    }
+}
+
+/// Returns the directories templates are loaded from for a shell version, from the
+/// lowest to the highest precedence: the source directory then the overlays which exist
+pub fn source_layers(
+    source_dir: &str,
+    overlays: &[String],
+    version: ShellVersion,
+) -> Result<Vec<String>, std::io::Error> {
+    let version = version_dir(version)?;
+    let mut layers = vec![format!("{}/{}", source_dir, version)];
+    for overlay in overlays {
+        let layer = format!("{}/{}", overlay, version);
+        if std::path::Path::new(&layer).is_dir() {
+            layers.push(layer);
+        }
+    }
+
+    Ok(layers)
+}
+
 pub struct Shell {
     pub version: ShellVersion,
     pub source_dir: String,
+    pub overlays: Vec<String>,
     pub preset: Preset,
+    pub strict: bool,
 }
 
 pub enum ThemeName {
@@ -14,22 +116,318 @@ pub enum ThemeName {
     Custom { name: String },
 }
 
+/// Shell sources loaded in memory, templates are parsed once and can then be
+/// rendered for every mode and accent without reading the source directory again
+pub struct ShellTemplates {
+    origins: BTreeMap<String, String>,
+    files: Vec<(String, Vec<u8>)>,
+    templates: Vec<String>,
+    registry: Handlebars<'static>,
+}
+
+/// A placeholder used by a template which isn't provided by the preset
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingVariable {
+    pub template: String,
+    pub line: usize,
+    pub column: usize,
+    pub name: String,
+}
+
+impl std::fmt::Display for MissingVariable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}: missing variable `{}`",
+            self.template, self.line, self.column, self.name
+        )
+    }
+}
+
+impl ShellTemplates {
+    pub fn load(source_path: &str) -> Result<ShellTemplates, std::io::Error> {
+        ShellTemplates::load_layers(&[source_path.to_string()])
+    }
+
+    /// Loads layered source directories, a file replaces the one producing the same
+    /// output in the layers before it, so `widgets/_panel.template` from the last layer
+    /// is used instead of the bundled one
+    pub fn load_layers(layers: &[String]) -> Result<ShellTemplates, std::io::Error> {
+        // output path -> (layer, relative path of the source)
+        let mut sources: BTreeMap<String, (String, String)> = BTreeMap::new();
+        for layer in layers {
+            for entry in WalkDir::new(layer).sort_by_file_name() {
+                let entry = entry?;
+                if !entry.file_type().is_file() {
+                    continue;
+                }
+
+                let relative = entry
+                    .path()
+                    .strip_prefix(layer)
+                    .map_err(std::io::Error::other)?
+                    .to_str()
+                    .unwrap()
+                    .to_string();
+                let output = match entry.path().extension().unwrap_or_default() == "template" {
+                    true => rendered_path(std::path::Path::new(&relative))
+                        .to_str()
+                        .unwrap()
+                        .to_string(),
+                    false => relative.clone(),
+                };
+                // in a layer, a template wins over the plain file it renders to
+                if let Some((previous, template)) = sources.get(&output) {
+                    if previous == layer && template.ends_with(".template") {
+                        continue;
+                    }
+                }
+                sources.insert(output, (layer.to_string(), relative));
+            }
+        }
+
+        let mut origins = BTreeMap::new();
+        let mut files = Vec::new();
+        let mut templates = Vec::new();
+        let mut registry = template_registry();
+
+        for (layer, relative) in sources.into_values() {
+            let path = std::path::Path::new(&layer).join(&relative);
+            if relative.ends_with(".template") {
+                let template = std::fs::read_to_string(path)?;
+                registry
+                    .register_template_string(&relative, template)
+                    .map_err(std::io::Error::other)?;
+                templates.push(relative.clone());
+            } else {
+                files.push((relative.clone(), std::fs::read(path)?));
+            }
+            origins.insert(relative, layer);
+        }
+
+        Ok(ShellTemplates {
+            origins,
+            files,
+            templates,
+            registry,
+        })
+    }
+
+    /// Returns every source file with the layer it was loaded from
+    pub fn sources(&self) -> Vec<(String, String)> {
+        self.origins
+            .iter()
+            .map(|(relative, layer)| (relative.to_string(), layer.to_string()))
+            .collect()
+    }
+
+    /// In strict mode, rendering fails on placeholders the preset doesn't provide instead
+    /// of replacing them with an empty string
+    pub fn strict(mut self, strict: bool) -> ShellTemplates {
+        self.registry.set_strict_mode(strict);
+        self
+    }
+
+    fn missing_variable(&self, err: &RenderError, name: &str) -> MissingVariable {
+        let template = err.template_name.clone().unwrap_or_default();
+        MissingVariable {
+            template: match self.origins.get(&template) {
+                Some(layer) => format!("{}/{}", layer, template),
+                None => template,
+            },
+            line: err.line_no.unwrap_or_default(),
+            column: err.column_no.unwrap_or_default(),
+            name: name.to_string(),
+        }
+    }
+
+    fn render_error(&self, err: RenderError) -> std::io::Error {
+        let message = match err.reason() {
+            RenderErrorReason::MissingVariable(Some(name)) => {
+                self.missing_variable(&err, name).to_string()
+            }
+            _ => err.to_string(),
+        };
+        std::io::Error::new(std::io::ErrorKind::InvalidData, message)
+    }
+
+    /// Renders every template in strict mode, for every mode and accent so placeholders
+    /// only used in a branch of one of them are found too, and returns all the placeholders
+    /// the preset doesn't provide. A missing placeholder is replaced by a black color to
+    /// keep rendering, so branches depending on its value are only checked for black
+    pub fn check(&self, preset: &Preset) -> Result<Vec<MissingVariable>, std::io::Error> {
+        let mut registry = self.registry.clone();
+        registry.set_strict_mode(true);
+
+        let mut missing = Vec::new();
+        for (mode, accent) in Shell::variants() {
+            preset
+                .tokens
+                .check_colors(&preset.template_data(mode, accent))?;
+            for name in &self.templates {
+                let mut data = preset.template_data(mode, accent);
+                loop {
+                    let err = match registry.render(name, &data) {
+                        Ok(_) => break,
+                        Err(err) => err,
+                    };
+                    match err.reason() {
+                        RenderErrorReason::MissingVariable(Some(variable))
+                            if !data.contains_key(variable) =>
+                        {
+                            let variable_missing = self.missing_variable(&err, variable);
+                            if !missing.contains(&variable_missing) {
+                                missing.push(variable_missing);
+                            }
+                            // keep rendering to find the next ones, with a color so helpers work
+                            data.insert(variable.to_string(), "#000000".to_string());
+                        }
+                        _ => return Err(self.render_error(err)),
+                    }
+                }
+            }
+        }
+
+        Ok(missing)
+    }
+
+    /// Returns the SVG assets of the shell theme, once rendered
+    pub fn assets(&self) -> Vec<String> {
+        self.files
+            .iter()
+            .map(|(relative, _)| relative.to_string())
+            .chain(self.templates.iter().map(|name| {
+                rendered_path(std::path::Path::new(name))
+                    .to_str()
+                    .unwrap()
+                    .to_string()
+            }))
+            .filter(|path| path.ends_with(".svg"))
+            .collect()
+    }
+
+    /// Writes the sources to `target_path`, each `.template` is rendered with the preset
+    /// and written next to it with the `.scss` extension (or without `.template` when the
+    /// name has another extension, like `.svg.template`), SVGs are recolored with the accent.
+    /// The widget snippets and variable overrides of the preset are applied to the SCSS
+    pub fn render(
+        &self,
+        preset: &Preset,
+        mode: Mode,
+        accent: AccentsColor,
+        target_path: &str,
+    ) -> Result<(), std::io::Error> {
+        let data = preset.template_data(mode, accent);
+        preset.tokens.check_colors(&data)?;
+        let mut widgets = preset.custom.shell_widgets.clone();
+        let mut overridden = BTreeSet::new();
+
+        // applies the preset overrides to a SCSS file, given its path in the output
+        let mut customize = |relative: &str, scss: String| {
+            let mut scss =
+                override_variables(&scss, &preset.custom.shell_variables, &mut overridden);
+            let widget = relative
+                .strip_prefix(SHELL_WIDGETS_DIR)
+                .and_then(|name| name.strip_prefix("/_"))
+                .and_then(|name| name.strip_suffix(".scss"));
+            if let Some(snippet) = widget.and_then(|name| widgets.remove(name)) {
+                scss += &format!("\n/* Preset: {} */\n{}\n", widget.unwrap(), snippet);
+            }
+            scss
+        };
+
+        for (relative, content) in &self.files {
+            let path = std::path::Path::new(target_path).join(relative);
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            if relative.ends_with(".svg") {
+                let svg = String::from_utf8_lossy(content);
+                std::fs::write(path, recolor_svg(&svg, &data))?;
+            } else if relative.ends_with(".scss") {
+                let scss = String::from_utf8_lossy(content).to_string();
+                std::fs::write(path, customize(relative, scss))?;
+            } else {
+                std::fs::write(path, content)?;
+            }
+        }
+
+        for name in &self.templates {
+            let path = std::path::Path::new(target_path).join(name);
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            let rendered = self
+                .registry
+                .render(name, &data)
+                .map_err(|err| self.render_error(err))?;
+            let rendered = replace_variant(rendered, &data);
+            let output = rendered_path(std::path::Path::new(name));
+            let output = output.to_str().unwrap();
+            match output.ends_with(".scss") {
+                true => std::fs::write(rendered_path(&path), customize(output, rendered))?,
+                false => std::fs::write(rendered_path(&path), rendered)?,
+            }
+        }
+
+        if let Some(widget) = widgets.keys().next() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Unknown shell widget {}", widget),
+            ));
+        }
+        if let Some(variable) = preset
+            .custom
+            .shell_variables
+            .keys()
+            .find(|name| !overridden.contains(*name))
+        {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Unknown shell variable ${}", variable),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
 impl Shell {
     pub fn new(source_dir: String, preset: Preset) -> Shell {
         let version = get_gnome_shell_version();
+        Shell::with_version(source_dir, preset, version)
+    }
+
+    /// Creates a shell theme for a given GNOME Shell version instead of the installed one
+    pub fn with_version(source_dir: String, preset: Preset, version: ShellVersion) -> Shell {
         Shell {
             version,
             source_dir,
+            overlays: Vec::new(),
             preset,
+            strict: false,
         }
     }
 
-    fn apply_gtk(&self, mode: Mode, accent: AccentsColor, theme_dir: String) {
+    /// Fails the build on template placeholders the preset doesn't provide
+    pub fn strict(mut self, strict: bool) -> Shell {
+        self.strict = strict;
+        self
+    }
+
+    /// Adds directories whose files take precedence over the source directory, each
+    /// one contains a directory per shell version like the source directory
+    pub fn overlays(mut self, overlays: Vec<String>) -> Shell {
+        self.overlays.extend(overlays);
+        self
+    }
+
+    fn apply_gtk(&self, mode: Mode, accent: AccentsColor, theme_dir: &str) {
         if !std::path::Path::new(&format!("{}/gtk-4.0", theme_dir)).exists() {
-            std::fs::create_dir_all(&format!("{}/gtk-4.0", theme_dir)).unwrap();
+            std::fs::create_dir_all(format!("{}/gtk-4.0", theme_dir)).unwrap();
         }
         if !std::path::Path::new(&format!("{}/gtk-3.0", theme_dir)).exists() {
-            std::fs::create_dir_all(&format!("{}/gtk-3.0", theme_dir)).unwrap();
+            std::fs::create_dir_all(format!("{}/gtk-3.0", theme_dir)).unwrap();
         }
 
         ApplyBuilder::new(self.preset.clone())
@@ -40,39 +438,44 @@ impl Shell {
             .apply();
     }
 
-    pub fn apply(
+    /// Returns the path of the templates matching the shell version
+    pub fn source_path(&self) -> Result<String, std::io::Error> {
+        Ok(format!("{}/{}", self.source_dir, version_dir(self.version)?))
+    }
+
+    /// Returns the source path followed by the existing overlays for the shell version
+    pub fn source_layers(&self) -> Result<Vec<String>, std::io::Error> {
+        source_layers(&self.source_dir, &self.overlays, self.version)
+    }
+
+    /// Loads the templates matching the shell version, they can be reused between builds
+    pub fn load_templates(&self) -> Result<ShellTemplates, std::io::Error> {
+        Ok(ShellTemplates::load_layers(&self.source_layers()?)?.strict(self.strict))
+    }
+
+    /// Returns the name of the theme created for the given mode and accent
+    pub fn theme_name(&self, mode: Mode, accent: AccentsColor) -> String {
+        format!("{}-{:?}-{:?}", self.preset.name, mode, accent)
+    }
+
+    /// Builds the shell and GTK themes in `themes_dir` from already loaded templates and
+    /// returns the path of the created theme
+    pub fn build(
         &self,
-        target_dir: String,
-        themes_dir: String,
+        templates: &ShellTemplates,
+        target_dir: &str,
+        themes_dir: &str,
         mode: Mode,
         accent: AccentsColor,
-        theme_name: ThemeName,
-        gsettings: bool,
-    ) -> Result<(), std::io::Error> {
-        let version = match self.version {
-            ShellVersion::G46 => "46",
-            ShellVersion::Unsupported => {
-                return Err(std::io::Error::new(
-                    std::io::ErrorKind::Other,
-                    "Unsupported shell version",
-                ))
-            }
-        };
-
-        let source_path = format!("{}/{}", self.source_dir, version);
+        theme_name: &ThemeName,
+    ) -> Result<String, std::io::Error> {
         let target_path = match theme_name {
-            ThemeName::Default => format!(
-                "{}/{}-{:?}-{:?}",
-                target_dir, self.preset.name, mode, accent
-            ),
-            ThemeName::Custom { ref name } => name.to_string(),
+            ThemeName::Default => format!("{}/{}", target_dir, self.theme_name(mode, accent)),
+            ThemeName::Custom { name } => name.to_string(),
         };
         let theme_dir = match theme_name {
-            ThemeName::Default => format!(
-                "{}/{}-{:?}-{:?}",
-                themes_dir, self.preset.name, mode, accent
-            ),
-            ThemeName::Custom { ref name } => name.to_string(),
+            ThemeName::Default => format!("{}/{}", themes_dir, self.theme_name(mode, accent)),
+            ThemeName::Custom { name } => name.to_string(),
         };
 
         if !std::path::Path::new(&target_path).exists() {
@@ -83,43 +486,153 @@ impl Shell {
             std::fs::create_dir_all(&theme_dir).unwrap();
         }
 
-        std::fs::create_dir_all(&format!("{}/gnome-shell", theme_dir)).unwrap();
+        std::fs::create_dir_all(format!("{}/gnome-shell", theme_dir)).unwrap();
 
-        let output = run_command(&format!("cp -r {}/* \"{}\"", source_path, target_path));
+        templates.render(&self.preset, mode, accent, &target_path)?;
 
-        if !output.status.success() {
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                "Failed to copy files",
-            ));
+        let css = from_path(
+            format!("{}/gnome-shell.scss", target_path),
+            &grass::Options::default(),
+        )
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err.to_string()))?;
+
+        // assets are copied next to the stylesheet, which then uses them instead of the
+        // ones bundled with GNOME Shell
+        let mut css = css;
+        for asset in templates.assets() {
+            let name = std::path::Path::new(&asset).file_name().unwrap().to_str().unwrap();
+            std::fs::copy(
+                format!("{}/{}", target_path, asset),
+                format!("{}/gnome-shell/{}", theme_dir, name),
+            )?;
+            css = css.replace(&format!("{}{}", SHELL_RESOURCE_PREFIX, name), name);
         }
+        std::fs::write(format!("{}/gnome-shell/gnome-shell.css", theme_dir), css)?;
+        self.apply_gtk(mode, accent, &theme_dir);
+
+        let name = std::path::Path::new(&theme_dir)
+            .file_name()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string();
+        std::fs::write(
+            format!("{}/index.theme", theme_dir),
+            self.preset.to_index_theme(&name, mode, accent),
+        )?;
 
-        for entry in WalkDir::new(&target_path) {
-            let entry = entry?;
-            if entry.path().extension().unwrap_or_default() == "template" {
-                let template = std::fs::read_to_string(entry.path())?;
-                let rendered = self.preset.render_template(template, mode, accent);
-                std::fs::write(entry.path().with_extension("scss"), rendered)?;
-                std::fs::remove_file(entry.path().with_extension("template"))?;
+        Ok(theme_dir)
+    }
+
+    /// Packs a built shell theme (stylesheet and assets) into a GResource bundle that
+    /// can replace `gnome-shell-theme.gresource`
+    pub fn build_gresource(theme_dir: &str, output: &str) -> Result<(), std::io::Error> {
+        Shell::add_to_gresource(theme_dir, GResourceBuilder::new())?.write(output)
+    }
+
+    /// Adds a built shell theme to a GResource bundle, replacing the files of the same name
+    pub fn add_to_gresource(
+        theme_dir: &str,
+        builder: GResourceBuilder,
+    ) -> Result<GResourceBuilder, std::io::Error> {
+        let css = std::fs::read(format!("{}/gnome-shell/gnome-shell.css", theme_dir))?;
+        let mut builder = builder;
+        for name in SHELL_STYLESHEETS {
+            builder = builder.file(&format!("{}{}", SHELL_RESOURCE_PATH, name), css.clone());
+        }
+
+        for entry in std::fs::read_dir(format!("{}/gnome-shell", theme_dir))? {
+            let path = entry?.path();
+            if path.extension().unwrap_or_default() == "svg" {
+                let name = path.file_name().unwrap().to_str().unwrap();
+                builder = builder.file(
+                    &format!("{}{}", SHELL_RESOURCE_PATH, name),
+                    std::fs::read(&path)?,
+                );
             }
         }
 
-        let css = from_path(
-            &format!("{}/gnome-shell.scss", target_path),
-            &grass::Options::default(),
-        )
-        .unwrap();
-        std::fs::write(format!("{}/gnome-shell/gnome-shell.css", theme_dir), css)?;
-        self.apply_gtk(mode, accent, theme_dir);
+        Ok(builder)
+    }
+
+    /// Builds every mode and accent combination of the preset, each in its own
+    /// `{name}-{mode}-{accent}` directory, and returns the paths of the created themes
+    pub fn build_all_variants(
+        &self,
+        target_dir: &str,
+        themes_dir: &str,
+    ) -> Result<Vec<String>, std::io::Error> {
+        let templates = self.load_templates()?;
+
+        Shell::variants()
+            .into_iter()
+            .map(|(mode, accent)| {
+                self.build(
+                    &templates,
+                    target_dir,
+                    themes_dir,
+                    mode,
+                    accent,
+                    &ThemeName::Default,
+                )
+            })
+            .collect()
+    }
+
+    /// Same as [`Shell::build_all_variants`] but variants are built in parallel
+    #[cfg(feature = "parallel")]
+    pub fn build_all_variants_parallel(
+        &self,
+        target_dir: &str,
+        themes_dir: &str,
+    ) -> Result<Vec<String>, std::io::Error> {
+        let templates = self.load_templates()?;
+
+        Shell::variants()
+            .into_par_iter()
+            .map(|(mode, accent)| {
+                self.build(
+                    &templates,
+                    target_dir,
+                    themes_dir,
+                    mode,
+                    accent,
+                    &ThemeName::Default,
+                )
+            })
+            .collect()
+    }
+
+    fn variants() -> Vec<(Mode, AccentsColor)> {
+        Mode::ALL
+            .iter()
+            .flat_map(|mode| AccentsColor::ALL.iter().map(move |accent| (*mode, *accent)))
+            .collect()
+    }
+
+    pub fn apply(
+        &self,
+        target_dir: String,
+        themes_dir: String,
+        mode: Mode,
+        accent: AccentsColor,
+        theme_name: ThemeName,
+        gsettings: bool,
+    ) -> Result<(), std::io::Error> {
+        let templates = self.load_templates()?;
+        self.build(
+            &templates,
+            &target_dir,
+            &themes_dir,
+            mode,
+            accent,
+            &theme_name,
+        )?;
 
         if gsettings {
-            set_shell_theme(
-                format!(
-                    "{}-{:?}-{:?}", self.preset.name, mode, accent
-                ).as_str()
-            );
+            set_shell_theme(self.theme_name(mode, accent).as_str());
         }
 
-        return Ok(());
+        Ok(())
     }
 }
@@ -1,39 +1,178 @@
 use crate::preset::Preset;
 #[cfg(feature = "online")]
-use reqwest::header::{HeaderMap, HeaderValue, USER_AGENT};
-use serde::Deserialize;
-
-#[cfg(feature = "online")]
-const GH_API_URL: &str =
-    "https://api.github.com/repos/t-dantiau/community/git/trees/main?recursive=1";
-
-#[cfg(feature = "online")]
-const GH_COMMUNITY_PRESETS_URL: &str = "https://github.com/t-dantiau/Community/raw/main";
+use crate::remote::{Remote, COMMUNITY_URL};
+use crate::utils::slugify;
+use serde::Serialize;
+use walkdir::WalkDir;
 
+/// Moved to [`crate::remote`], which handles every remote
+pub use crate::remote::{GhApiUrlResponse, Tree};
 
+/// A store of presets. Presets are saved in `base_path`, and more presets can be read from
+/// read-only layers, e.g. presets installed for every user. A preset hides the presets with
+/// the same ID of the layers before it, and the presets of `base_path` hide all of them
 pub struct Store {
     pub base_path: String,
-    presets: Vec<Preset>,
+    layers: Vec<String>,
+    presets: Vec<StoredPreset>,
+    recursive: bool,
+    /// Files of replaced presets saved under another name, like `{name}.json` before
+    /// presets had an ID, and the file they are renamed to when the store is saved
+    renamed: Vec<(String, String)>,
 }
 
-#[derive(Deserialize, Debug)]
-pub struct GhApiUrlResponse {
-    pub tree: Vec<Tree>,
-    pub url: String,
-    pub sha: String,
-    pub truncated: bool,
+struct StoredPreset {
+    path: String,
+    /// Directory of the layer the preset was loaded from
+    layer: String,
+    preset: Preset,
 }
 
-#[derive(Deserialize, Debug)]
-pub struct Tree {
+/// What happened to the files of the store when it was loaded
+#[derive(Debug, Default)]
+pub struct LoadReport {
+    pub loaded: usize,
+    /// Files which aren't presets, like a README or an editor swap file
+    pub skipped: Vec<String>,
+    /// Presets written with an older version of the preset format
+    pub outdated: Vec<String>,
+    /// Files which couldn't be read or parsed, they aren't in the store
+    pub errors: Vec<(String, std::io::Error)>,
+    /// Presets hidden by a preset with the same ID in a later layer
+    pub hidden: Vec<String>,
+}
+
+/// What the store knows about a preset, for listing and searching presets
+#[derive(Serialize, Debug, Clone)]
+pub struct IndexEntry {
+    pub id: String,
+    pub name: String,
+    pub author: String,
+    pub description: String,
+    pub version: String,
+    pub license: String,
+    pub modes: Vec<String>,
+    pub accents: Vec<String>,
     pub path: String,
-    pub mode: String,
+    /// Directory of the layer the preset comes from
+    pub layer: String,
+    /// Whether the preset is in a read-only layer
+    pub read_only: bool,
+}
+
+impl IndexEntry {
+    fn new(stored: &StoredPreset, base_path: &str) -> IndexEntry {
+        let preset = &stored.preset;
+        IndexEntry {
+            id: preset.id(),
+            name: preset.name.clone(),
+            author: preset.author.name.clone(),
+            description: preset.description.clone(),
+            version: preset.version.clone(),
+            license: preset.license.to_string(),
+            modes: preset.supported.mode.clone(),
+            accents: preset.supported.accents.clone(),
+            path: stored.path.clone(),
+            layer: stored.layer.clone(),
+            read_only: stored.layer != base_path,
+        }
+    }
+}
 
-    #[serde(rename(deserialize = "type"))]
-    pub type_: String,
-    pub sha: String,
-    pub size: u32,
-    pub url: String,
+/// A search in the store, the text is matched fuzzily against the name, author and
+/// description, and the filters must all match
+#[derive(Debug, Clone, Default)]
+pub struct Search {
+    text: String,
+    mode: Option<String>,
+    accent: Option<String>,
+    author: Option<String>,
+    license: Option<String>,
+}
+
+impl Search {
+    pub fn new(text: &str) -> Search {
+        Search {
+            text: text.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Only keeps presets supporting this mode
+    pub fn mode(mut self, mode: Option<String>) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Only keeps presets supporting this accent
+    pub fn accent(mut self, accent: Option<String>) -> Self {
+        self.accent = accent;
+        self
+    }
+
+    /// Only keeps presets whose author contains this text
+    pub fn author(mut self, author: Option<String>) -> Self {
+        self.author = author;
+        self
+    }
+
+    /// Only keeps presets whose license contains this text, e.g. `MIT` or `CC-BY`
+    pub fn license(mut self, license: Option<String>) -> Self {
+        self.license = license;
+        self
+    }
+
+    fn matches_filters(&self, entry: &IndexEntry) -> bool {
+        let contains = |values: &[String], value: &Option<String>| match value {
+            Some(value) => values.iter().any(|v| v.eq_ignore_ascii_case(value)),
+            None => true,
+        };
+        let contains_text = |text: &str, value: &Option<String>| match value {
+            Some(value) => text.to_lowercase().contains(&value.to_lowercase()),
+            None => true,
+        };
+
+        contains(&entry.modes, &self.mode)
+            && contains(&entry.accents, &self.accent)
+            && contains_text(&entry.author, &self.author)
+            && contains_text(&entry.license, &self.license)
+    }
+
+    /// Returns how well the entry matches the text, or `None` when it doesn't. The name
+    /// counts more than the author, which counts more than the description
+    fn score(&self, entry: &IndexEntry) -> Option<u32> {
+        if self.text.trim().is_empty() {
+            return Some(0);
+        }
+
+        [(&entry.name, 3), (&entry.author, 2), (&entry.description, 1)]
+            .iter()
+            .filter_map(|(field, weight)| fuzzy_score(&self.text, field).map(|s| s * weight))
+            .max()
+    }
+}
+
+/// Matches the characters of `query` in order in `text`, ignoring case and spaces.
+/// Consecutive characters and characters starting a word score more
+fn fuzzy_score(query: &str, text: &str) -> Option<u32> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous: Option<usize> = None;
+
+    for c in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = position + text[position..].iter().position(|t| *t == c)?;
+        score += 1;
+        if previous.is_some_and(|p| p + 1 == found) {
+            score += 2;
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 2;
+        }
+        previous = Some(found);
+        position = found + 1;
+    }
+    Some(score)
 }
 
 impl Store {
@@ -44,95 +183,241 @@ impl Store {
 
         Store {
             base_path,
+            layers: Vec::new(),
             presets: Vec::new(),
+            recursive: false,
+            renamed: Vec::new(),
         }
     }
 
-    pub fn load(&mut self) {
-        let paths = std::fs::read_dir(&self.base_path).unwrap();
+    /// Read-only directories to load presets from, the last one wins, missing directories
+    /// are ignored
+    pub fn layers(mut self, layers: Vec<String>) -> Self {
+        self.layers = layers;
+        self
+    }
+
+    /// Also loads the presets of subdirectories
+    pub fn recursive(mut self, recursive: bool) -> Self {
+        self.recursive = recursive;
+        self
+    }
+
+    /// Loads the `.json` files of the layers and of the store, hidden files and other files
+    /// are skipped and the files which can't be loaded are listed in the report
+    pub fn load(&mut self) -> LoadReport {
+        let mut report = LoadReport::default();
+        let mut layers = self.layers.clone();
+        layers.push(self.base_path.clone());
 
-        for path in paths {
-            let path = path.unwrap().path();
-            let preset = Preset::from_file(&path.to_str().unwrap());
-            self.presets.push(preset);
+        for layer in layers {
+            if !std::path::Path::new(&layer).is_dir() {
+                continue;
+            }
+            let loaded = self.presets.len();
+            self.load_layer(&layer, &mut report);
+
+            let ids: Vec<String> = self.presets[loaded..]
+                .iter()
+                .map(|p| p.preset.id())
+                .collect();
+            let (kept, hidden): (Vec<StoredPreset>, Vec<StoredPreset>) = self
+                .presets
+                .drain(..loaded)
+                .partition(|p| !ids.contains(&p.preset.id()));
+            report.hidden.extend(hidden.into_iter().map(|p| p.path));
+            self.presets.splice(0..0, kept);
         }
+        report
     }
 
+    fn load_layer(&mut self, layer: &str, report: &mut LoadReport) {
+        let walker = WalkDir::new(layer)
+            .min_depth(1)
+            .max_depth(if self.recursive { usize::MAX } else { 1 })
+            .sort_by_file_name()
+            .into_iter()
+            .filter_entry(|entry| !entry.file_name().to_string_lossy().starts_with('.'));
+
+        for entry in walker {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(error) => {
+                    let path = error.path().map(|p| p.display().to_string());
+                    report.errors.push((path.unwrap_or_default(), error.into()));
+                    continue;
+                }
+            };
+            let path = entry.path().to_string_lossy().to_string();
+            if entry.file_type().is_dir() {
+                continue;
+            }
+            if entry.path().extension() != Some("json".as_ref()) {
+                report.skipped.push(path);
+                continue;
+            }
+
+            match Preset::from_file_migrated(&path) {
+                Ok((preset, migration)) => {
+                    if !migration.is_empty() {
+                        report.outdated.push(path.clone());
+                    }
+                    self.presets.push(StoredPreset {
+                        path,
+                        layer: layer.to_string(),
+                        preset,
+                    });
+                    report.loaded += 1;
+                }
+                Err(error) => report.errors.push((path, error)),
+            }
+        }
+    }
+
+    /// Adds a preset, it's saved in a file named after its ID and replaces the preset of the
+    /// store with the same ID, whose file is renamed if it had another name. It hides the
+    /// presets of the layers with the same ID
     pub fn add_preset(&mut self, preset: Preset) {
-        self.presets.push(preset);
+        let path = format!("{}/{}.json", self.base_path, preset.id());
+        let id = preset.id();
+        for stored in &self.presets {
+            if stored.layer == self.base_path && stored.path != path && stored.preset.id() == id {
+                self.renamed.push((stored.path.clone(), path.clone()));
+            }
+        }
+        self.presets.retain(|p| p.path != path && p.preset.id() != id);
+        self.presets.push(StoredPreset {
+            path,
+            layer: self.base_path.clone(),
+            preset,
+        });
     }
 
-    pub fn remove_preset(&mut self, name: String) {
-        self.presets.retain(|p| p.name != name);
+    /// Removes a preset of the store, presets of the read-only layers can't be removed
+    pub fn remove_preset(&mut self, query: &str) -> Result<(), std::io::Error> {
+        let index = self.find(query)?;
+        if self.presets[index].layer != self.base_path {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::PermissionDenied,
+                format!(
+                    "{} is in the read-only layer {}",
+                    self.presets[index].path, self.presets[index].layer
+                ),
+            ));
+        }
+        let stored = self.presets.remove(index);
+        std::fs::remove_file(stored.path)
+    }
 
-        let path = format!("{}/{}.json", self.base_path, name);
-        std::fs::remove_file(path).unwrap();
+    /// Finds a preset by ID, slug of its name, filename with or without extension, or
+    /// name ignoring case, in this order
+    pub fn get_preset(&self, query: &str) -> Result<&Preset, std::io::Error> {
+        Ok(&self.presets[self.find(query)?].preset)
     }
 
-    pub fn get_preset(&self, name: String) -> Option<&Preset> {
-        self.presets.iter().find(|p| p.name == name)
+    fn find(&self, query: &str) -> Result<usize, std::io::Error> {
+        // how closely each preset matches, 0 for its ID down to 3 for its name
+        let levels: Vec<Option<usize>> = self
+            .presets
+            .iter()
+            .map(|StoredPreset { path, preset, .. }| {
+                let path = std::path::Path::new(path);
+                let filename = |name: Option<&std::ffi::OsStr>| {
+                    name.is_some_and(|name| name.to_string_lossy() == query)
+                };
+                [
+                    preset.id() == query,
+                    slugify(&preset.name) == query,
+                    filename(path.file_name()) || filename(path.file_stem()),
+                    preset.name.to_lowercase() == query.to_lowercase(),
+                ]
+                .iter()
+                .position(|matches| *matches)
+            })
+            .collect();
+
+        let best = levels.iter().flatten().min().ok_or(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("no preset matches {}", query),
+        ))?;
+        let found: Vec<usize> = (0..levels.len())
+            .filter(|i| levels[*i] == Some(*best))
+            .collect();
+        if found.len() > 1 {
+            let paths: Vec<&str> = found
+                .iter()
+                .map(|i| self.presets[*i].path.as_str())
+                .collect();
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("{} matches several presets: {}", query, paths.join(", ")),
+            ));
+        }
+        Ok(found[0])
     }
 
+    /// Saves the presets of the store, the read-only layers aren't changed
     pub fn save_presets(&self) {
-        for preset in &self.presets {
-            let path = format!("{}/{}.json", self.base_path, preset.name);
-            preset.to_file(&path);
+        // renamed rather than removed, so a case-insensitive filesystem keeps the preset
+        for (from, to) in &self.renamed {
+            if std::path::Path::new(from).exists() {
+                std::fs::rename(from, to).unwrap();
+            }
+        }
+        for stored in self.presets.iter().filter(|p| p.layer == self.base_path) {
+            stored.preset.to_file(&stored.path);
         }
     }
 
-    #[cfg(feature = "online")]
-    fn construct_headers() -> HeaderMap {
-        let mut headers = HeaderMap::new();
-        headers.insert(USER_AGENT, HeaderValue::from_static("reqwest"));
-        headers
+    /// Returns the metadata of every preset, sorted by name
+    pub fn index(&self) -> Vec<IndexEntry> {
+        let mut index: Vec<IndexEntry> = self
+            .presets
+            .iter()
+            .map(|stored| IndexEntry::new(stored, &self.base_path))
+            .collect();
+        index.sort_by_key(|entry| entry.name.to_lowercase());
+        index
+    }
+
+    /// Returns the presets matching the search, best matches first
+    pub fn search(&self, search: &Search) -> Vec<IndexEntry> {
+        let mut results: Vec<(u32, IndexEntry)> = self
+            .index()
+            .into_iter()
+            .filter(|entry| search.matches_filters(entry))
+            .filter_map(|entry| Some((search.score(&entry)?, entry)))
+            .collect();
+        // the sort is stable, so equal scores stay sorted by name
+        results.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        results.into_iter().map(|(_, entry)| entry).collect()
     }
 
+    pub fn list_local_presets(&self) -> Vec<String> {
+        self.presets.iter().map(|p| p.path.clone()).collect()
+    }
+
+    /// Returns the URLs of the community presets, panics when they can't be listed since
+    /// the signature has no room for an error
     #[cfg(feature = "online")]
+    #[deprecated(note = "use `remote::Remote::list`, which supports other remotes")]
     pub fn list_online_presets(&self) -> Vec<String> {
-        let client = reqwest::blocking::Client::new();
-        let res: GhApiUrlResponse = client
-            .get(GH_API_URL)
-            .headers(Store::construct_headers())
-            .send()
+        Remote::community()
+            .list()
             .unwrap()
-            .json()
-            .unwrap();
-
-        let mut online_presets = Vec::new();
-
-        for tree in res.tree {
-            let url = format!(
-                "{}/{}",
-                GH_COMMUNITY_PRESETS_URL, tree.path
-            );
-            online_presets.push(url);
-        }
-        online_presets
+            .into_iter()
+            .map(|preset| preset.url)
+            .collect()
     }
 
     #[cfg(feature = "online")]
+    #[deprecated(note = "use `remote::download`, which supports other remotes")]
     pub fn download_online_preset(&self, name: String) -> Result<Preset, reqwest::Error> {
-        let client = reqwest::blocking::Client::new();
-
-        let url = format!(
-            "{}/{}.json",
-            GH_COMMUNITY_PRESETS_URL, name
-        );
-
-        let resp = client
-            .get(&url)
-            .headers(Store::construct_headers())
-            .send()
-            .unwrap();
-        let content: Preset = resp.json().unwrap();
-        Ok(content)
-    }
-
-    pub fn list_local_presets(&self) -> Vec<String> {
-        let mut local_presets = Vec::new();
-        for preset in &self.presets {
-            local_presets.push(format!("{}/{}.json", self.base_path, preset.name));
-        }
-        local_presets
+        reqwest::blocking::Client::new()
+            .get(format!("{}/raw/main/{}.json", COMMUNITY_URL, name))
+            .header(reqwest::header::USER_AGENT, "reqwest")
+            .send()?
+            .error_for_status()?
+            .json()
     }
 }
@@ -0,0 +1,318 @@
+use crate::color::Color;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LengthUnit {
+    Px,
+    Pt,
+    Em,
+}
+
+impl LengthUnit {
+    fn suffix(&self) -> &'static str {
+        match self {
+            LengthUnit::Px => "px",
+            LengthUnit::Pt => "pt",
+            LengthUnit::Em => "em",
+        }
+    }
+}
+
+/// A CSS length such as `12px` or `1.5em`, a number without unit is in pixels
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Length {
+    pub value: f64,
+    pub unit: LengthUnit,
+}
+
+impl Length {
+    pub fn px(value: f64) -> Length {
+        Length {
+            value,
+            unit: LengthUnit::Px,
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Length> {
+        let value = value.trim();
+        for unit in [LengthUnit::Px, LengthUnit::Pt, LengthUnit::Em] {
+            if let Some(number) = value.strip_suffix(unit.suffix()) {
+                return number
+                    .trim()
+                    .parse()
+                    .ok()
+                    .map(|value| Length { value, unit });
+            }
+        }
+        value.parse().ok().map(Length::px)
+    }
+
+    /// Returns the length in points, em are relative to `font_size`, in points too
+    fn to_pt(self, font_size: f64) -> f64 {
+        match self.unit {
+            LengthUnit::Px => self.value * 0.75,
+            LengthUnit::Pt => self.value,
+            LengthUnit::Em => self.value * font_size,
+        }
+    }
+
+    /// Converts a length in em to points so it can be added to absolute SCSS lengths
+    fn absolute(self, font_size: f64) -> Length {
+        match self.unit {
+            LengthUnit::Em => Length {
+                value: self.to_pt(font_size),
+                unit: LengthUnit::Pt,
+            },
+            _ => self,
+        }
+    }
+}
+
+/// Size of the shell font in points when the preset doesn't set one
+const SHELL_FONT_SIZE: f64 = 11.0;
+
+impl std::fmt::Display for Length {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.value, self.unit.suffix())
+    }
+}
+
+impl Serialize for Length {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Length {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Length, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Number(f64),
+            Text(String),
+        }
+
+        match Raw::deserialize(deserializer)? {
+            Raw::Number(value) => Ok(Length::px(value)),
+            Raw::Text(text) => Length::parse(&text)
+                .ok_or_else(|| serde::de::Error::custom(format!("invalid length {}", text))),
+        }
+    }
+}
+
+#[cfg(feature = "schema")]
+impl schemars::JsonSchema for Length {
+    fn schema_name() -> String {
+        "Length".to_string()
+    }
+
+    fn json_schema(_: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        serde_json::from_value(serde_json::json!({
+            "description": "A CSS length such as `12px` or `1.5em`, a number is in pixels",
+            "anyOf": [
+                { "type": "number" },
+                { "type": "string", "pattern": "^\\s*-?[0-9.]+\\s*(px|pt|em)?\\s*$" }
+            ]
+        }))
+        .unwrap()
+    }
+}
+
+/// A font, missing fields keep the font of the desktop
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Font {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub family: Option<String>,
+    /// CSS weight, from 100 to 900
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weight: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<Length>,
+}
+
+fn default_tint_strength() -> f64 {
+    20.0
+}
+
+/// A color mixed into a background
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Tint {
+    /// Any CSS color or `@name` reference to a preset color
+    pub color: String,
+    /// Percentage of the tint in the background
+    #[serde(default = "default_tint_strength")]
+    pub strength: f64,
+}
+
+/// Design tokens of a preset which aren't colors, every token is optional and the
+/// Adwaita value is used when it isn't set
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Tokens {
+    /// Corner radius of windows, and of shell popovers and dialogs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub window_radius: Option<Length>,
+    /// Corner radius of buttons, and of most shell elements
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub button_radius: Option<Length>,
+    /// Padding inside shell elements, GTK keeps its own padding
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spacing: Option<Length>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub font: Option<Font>,
+    /// Opacity of the shell top panel, from 0 to 1
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub panel_opacity: Option<f64>,
+    /// Opacity of the shell dash, from 0 to 1
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dash_opacity: Option<f64>,
+    /// Color mixed into the shell overview background
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub overview_tint: Option<Tint>,
+}
+
+/// Follows `@name` references to the other template values
+fn resolve_color(colors: &BTreeMap<String, String>, value: &str) -> Option<Color> {
+    let mut value = value;
+    for _ in 0..8 {
+        match value.strip_prefix('@') {
+            Some(name) => value = colors.get(name)?,
+            None => break,
+        }
+    }
+    Color::parse(value)
+}
+
+impl Tokens {
+    /// Returns the GTK rules applying the radius and font tokens, empty when none is set,
+    /// the spacing, opacity and tint tokens only apply to the shell
+    pub fn to_css(&self) -> String {
+        let mut css = String::new();
+
+        if let Some(radius) = self.window_radius {
+            css += &format!("window.csd {{ border-radius: {}; }}\n", radius);
+        }
+        if let Some(radius) = self.button_radius {
+            css += &format!("button {{ border-radius: {}; }}\n", radius);
+        }
+        if let Some(font) = &self.font {
+            let mut rule = String::new();
+            if let Some(family) = &font.family {
+                rule += &format!(" font-family: \"{}\";", family);
+            }
+            if let Some(weight) = font.weight {
+                rule += &format!(" font-weight: {};", weight);
+            }
+            if let Some(size) = font.size {
+                rule += &format!(" font-size: {};", size);
+            }
+            if !rule.is_empty() {
+                css += &format!("window {{{} }}\n", rule);
+            }
+        }
+
+        css
+    }
+
+    /// Checks that the overview tint is a color or a reference to one of `colors`, shell
+    /// templates would otherwise use the upstream overview background
+    pub fn check_colors(&self, colors: &BTreeMap<String, String>) -> Result<(), std::io::Error> {
+        match &self.overview_tint {
+            Some(tint) if resolve_color(colors, &tint.color).is_none() => {
+                Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("Unable to resolve the overview tint color {}", tint.color),
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the values given to shell templates, unset lengths and backgrounds are the
+    /// upstream SCSS values and unset font family and weight are empty so templates can
+    /// always use them. `colors` are the preset colors, used to resolve the panel color.
+    /// Lengths in em are given in points since the SCSS adds them to px and pt lengths, the
+    /// font size is relative to the shell font and the other lengths to the font size.
+    /// Opacities multiply the alpha of the colors, see [`Tokens::check_colors`] for the tint
+    pub fn template_data(&self, colors: &BTreeMap<String, String>) -> BTreeMap<String, String> {
+        let font = self.font.clone().unwrap_or_default();
+        let font_size = font.size.map(|size| size.absolute(SHELL_FONT_SIZE));
+        let base = font_size.map_or(SHELL_FONT_SIZE, |size| size.to_pt(SHELL_FONT_SIZE));
+        let value = |length: Option<Length>, default: &str| match length {
+            Some(length) => length.absolute(base).to_string(),
+            None => default.to_string(),
+        };
+
+        let mut data = BTreeMap::new();
+        data.insert(
+            "window_radius".to_string(),
+            value(self.window_radius, "$base_border_radius * 2"),
+        );
+        data.insert(
+            "button_radius".to_string(),
+            value(self.button_radius, "8px"),
+        );
+        data.insert("spacing".to_string(), value(self.spacing, "6px"));
+        data.insert("font_size".to_string(), value(font_size, "11pt"));
+        data.insert(
+            "font_family".to_string(),
+            font.family
+                .map(|family| format!("\"{}\"", family))
+                .unwrap_or_default(),
+        );
+        data.insert(
+            "font_weight".to_string(),
+            font.weight
+                .map(|weight| weight.to_string())
+                .unwrap_or_default(),
+        );
+
+        let panel_color = colors
+            .get("panel_bg_color")
+            .and_then(|color| resolve_color(colors, color));
+        data.insert(
+            "panel_background".to_string(),
+            match (self.panel_opacity, panel_color) {
+                (Some(opacity), Some(color)) => color
+                    .with_alpha(color.alpha * opacity.clamp(0.0, 1.0))
+                    .to_css(),
+                // colors the preset doesn't resolve, like SCSS expressions, are left to Sass
+                (Some(opacity), None) => format!(
+                    "rgba($panel_bg_color, alpha($panel_bg_color) * {})",
+                    opacity.clamp(0.0, 1.0)
+                ),
+                (None, _) => "$panel_bg_color".to_string(),
+            },
+        );
+        // the dash and overview colors are computed by the SCSS, so are their variants
+        data.insert(
+            "dash_background".to_string(),
+            match self.dash_opacity {
+                Some(opacity) => format!(
+                    "rgba($system_overlay_bg_color, alpha($system_overlay_bg_color) * {})",
+                    opacity.clamp(0.0, 1.0)
+                ),
+                None => "$system_overlay_bg_color".to_string(),
+            },
+        );
+        data.insert(
+            "overview_background".to_string(),
+            match self
+                .overview_tint
+                .as_ref()
+                .and_then(|tint| Some((resolve_color(colors, &tint.color)?, tint.strength)))
+            {
+                Some((color, strength)) => format!(
+                    "mix({}, $system_base_color, {}%)",
+                    color.to_css(),
+                    strength.clamp(0.0, 100.0)
+                ),
+                None => "$system_base_color".to_string(),
+            },
+        );
+        data
+    }
+}
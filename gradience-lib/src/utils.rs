@@ -1,6 +1,6 @@
 pub fn run_command(command: &str) -> std::process::Output {
     // if os var FLATPAK_ID exists prefix command
-    if let Ok(_) = std::env::var("FLATPAK_ID") {
+    if std::env::var("FLATPAK_ID").is_ok() {
         std::process::Command::new("flatpak-spawn")
             .arg("--host")
             .arg("sh")
@@ -15,24 +15,31 @@ pub fn run_command(command: &str) -> std::process::Output {
             .output()
             .expect("failed to execute process")
     }
-
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum ShellVersion {
     G46,
     Unsupported
 }
 
+impl ShellVersion {
+    /// Parses a version like `46` or `46.2`
+    pub fn parse(version: &str) -> ShellVersion {
+        match version.split('.').next().unwrap_or_default().parse::<u32>() {
+            Ok(46) => ShellVersion::G46,
+            _ => ShellVersion::Unsupported,
+        }
+    }
+}
+
 pub fn get_gnome_shell_version() -> ShellVersion {
     let output = run_command("gnome-shell --version");
     let version = String::from_utf8(output.stdout).unwrap();
-    let version = version.split_whitespace().collect::<Vec<&str>>()[2].to_string();
-    let version = version.split(".").collect::<Vec<&str>>()[0].parse::<u32>().unwrap();
 
-    match version {
-        46 => ShellVersion::G46,
-        _ => ShellVersion::Unsupported,
+    match version.split_whitespace().nth(2) {
+        Some(version) => ShellVersion::parse(version),
+        None => ShellVersion::Unsupported,
     }
 }
 
@@ -59,4 +66,47 @@ pub fn set_shell_theme(theme: &str) {
 
 pub fn reset_shell_theme() {
     run_command("gsettings reset org.gnome.shell.extensions.user-theme name");
-}
\ No newline at end of file
+}
+
+/// Turns a name into a lowercase, filesystem-safe identifier, e.g. `Rosé Pine` into
+/// `rose-pine`. Names without any latin letter or digit get a hash of the name instead
+pub fn slugify(name: &str) -> String {
+    const FOLDS: [(&str, char); 13] = [
+        ("àáâãäåā", 'a'),
+        ("çćč", 'c'),
+        ("ďđ", 'd'),
+        ("èéêëēęě", 'e'),
+        ("ìíîïī", 'i'),
+        ("ñńň", 'n'),
+        ("òóôõöøō", 'o'),
+        ("ŕř", 'r'),
+        ("śšß", 's'),
+        ("ťţ", 't'),
+        ("ùúûüūů", 'u'),
+        ("ýÿ", 'y'),
+        ("źżž", 'z'),
+    ];
+
+    let mut slug = String::new();
+    for c in name.to_lowercase().chars() {
+        let c = FOLDS
+            .iter()
+            .find(|(from, _)| from.contains(c))
+            .map_or(c, |(_, to)| *to);
+        if c.is_ascii_alphanumeric() {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug = slug.trim_end_matches('-').to_string();
+
+    if slug.is_empty() {
+        // FNV-1a, unlike the std hasher it's the same with every Rust version
+        let hash = name.bytes().fold(0x811c9dc5u32, |hash, byte| {
+            (hash ^ byte as u32).wrapping_mul(0x01000193)
+        });
+        return format!("preset-{:08x}", hash);
+    }
+    slug
+}
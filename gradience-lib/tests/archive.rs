@@ -0,0 +1,122 @@
+#![cfg(feature = "archive")]
+
+mod common;
+
+use gradience_lib::archive::{export_theme, import_theme};
+use std::io::Write;
+
+const THEME_FILES: [&str; 3] = [
+    "gnome-shell/gnome-shell.css",
+    "gtk-4.0/gtk.css",
+    "index.theme",
+];
+
+/// Creates a built theme with a file outside of the theme layout
+fn theme(dir: &str) {
+    for file in THEME_FILES.iter().chain(&["README.md"]) {
+        let path = format!("{}/{}", dir, file);
+        std::fs::create_dir_all(std::path::Path::new(&path).parent().unwrap()).unwrap();
+        std::fs::write(path, format!("/* {} */", file)).unwrap();
+    }
+}
+
+#[test]
+fn exported_themes_are_imported_back() {
+    let dir = common::temp_dir();
+    let theme_dir = common::path(&dir, "Nord-Dark-Blue");
+    theme(&theme_dir);
+
+    for archive in ["nord.tar.xz", "nord.ZIP"] {
+        let archive = common::path(&dir, archive);
+        let themes_dir = common::path(&dir, "themes");
+        export_theme(&theme_dir, &archive).unwrap();
+
+        let imported = import_theme(&archive, &themes_dir).unwrap();
+        assert_eq!(imported, format!("{}/Nord-Dark-Blue", themes_dir));
+        for file in THEME_FILES {
+            assert_eq!(
+                std::fs::read_to_string(format!("{}/{}", imported, file)).unwrap(),
+                format!("/* {} */", file)
+            );
+        }
+        assert!(!std::path::Path::new(&format!("{}/README.md", imported)).exists());
+        std::fs::remove_dir_all(themes_dir).unwrap();
+    }
+}
+
+#[test]
+fn archives_are_named_after_the_file_in_any_case() {
+    let dir = common::temp_dir();
+    let archive = common::path(&dir, "Flat.ZIP");
+    let mut writer = zip::ZipWriter::new(std::fs::File::create(&archive).unwrap());
+    writer
+        .start_file("index.theme", zip::write::SimpleFileOptions::default())
+        .unwrap();
+    writer.finish().unwrap();
+
+    let themes_dir = common::path(&dir, "themes");
+    assert_eq!(
+        import_theme(&archive, &themes_dir).unwrap(),
+        format!("{}/Flat", themes_dir)
+    );
+}
+
+#[test]
+fn paths_outside_of_the_theme_are_rejected() {
+    let dir = common::temp_dir();
+    let themes_dir = common::path(&dir, "themes");
+    std::fs::create_dir_all(format!("{}/Other", themes_dir)).unwrap();
+
+    let zip = |name: &str, files: &[&str]| {
+        let archive = common::path(&dir, name);
+        let mut writer = zip::ZipWriter::new(std::fs::File::create(&archive).unwrap());
+        for file in files {
+            writer
+                .start_file(*file, zip::write::SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(b"evil").unwrap();
+        }
+        writer.finish().unwrap();
+        archive
+    };
+
+    let traversal = zip("traversal.zip", &["../evil/index.theme"]);
+    assert!(import_theme(&traversal, &themes_dir).is_err());
+    assert!(!std::path::Path::new(&common::path(&dir, "evil")).exists());
+
+    // a theme named after `.zip` would replace the whole themes directory
+    let unnamed = zip(".zip", &["index.theme"]);
+    assert!(import_theme(&unnamed, &themes_dir).is_err());
+    assert!(std::path::Path::new(&format!("{}/Other", themes_dir)).is_dir());
+}
+
+#[test]
+fn archives_of_the_current_directory_are_imported() {
+    // made with `tar -C dir -cJf theme.tar.xz .`, every entry starts with `./`
+    let dir = common::temp_dir();
+    let archive = common::path(&dir, "theme.tar.xz");
+    let file = std::fs::File::create(&archive).unwrap();
+    let mut builder = tar::Builder::new(xz2::write::XzEncoder::new(file, 6));
+    for file in THEME_FILES {
+        let content = format!("/* {} */", file);
+        let name = format!("./Nord-Dark-Blue/{}", file);
+        let mut header = tar::Header::new_gnu();
+        // the name is written as is, `set_path` would remove the `./` prefix
+        header.as_gnu_mut().unwrap().name[..name.len()].copy_from_slice(name.as_bytes());
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append(&header, content.as_bytes()).unwrap();
+    }
+    builder.into_inner().unwrap().finish().unwrap();
+
+    let themes_dir = common::path(&dir, "themes");
+    let imported = import_theme(&archive, &themes_dir).unwrap();
+    assert_eq!(imported, format!("{}/Nord-Dark-Blue", themes_dir));
+    for file in THEME_FILES {
+        assert_eq!(
+            std::fs::read_to_string(format!("{}/{}", imported, file)).unwrap(),
+            format!("/* {} */", file)
+        );
+    }
+}
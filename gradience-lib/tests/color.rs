@@ -0,0 +1,157 @@
+use gradience_lib::color::Color;
+use gradience_lib::helpers::template_registry;
+
+/// Evaluates a color expression with Sass, the reference the color functions follow
+fn sass(expression: &str) -> Color {
+    let css = grass::from_string(
+        format!("a {{ color: {}; }}", expression),
+        &grass::Options::default(),
+    )
+    .unwrap();
+    let value = css.split_once(": ").unwrap().1.split_once(';').unwrap().0;
+    Color::parse(value).unwrap()
+}
+
+fn assert_close(actual: Color, expected: Color) {
+    let channels = |c: Color| [c.red, c.green, c.blue, c.alpha];
+    for (a, e) in channels(actual).iter().zip(channels(expected)) {
+        assert!(
+            (a - e).abs() <= 1.0 / 255.0,
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+    }
+}
+
+#[test]
+fn colors_are_parsed() {
+    let css = |value: &str| Color::parse(value).map(|c| c.to_css());
+
+    assert_eq!(css("#3584E4").as_deref(), Some("#3584e4"));
+    assert_eq!(css("#fff").as_deref(), Some("#ffffff"));
+    assert_eq!(css("#0008").as_deref(), Some("rgba(0, 0, 0, 0.533)"));
+    assert_eq!(css(" rgb(53, 132, 228) ").as_deref(), Some("#3584e4"));
+    assert_eq!(
+        css("RGBA(0, 0, 0, 50%)").as_deref(),
+        Some("rgba(0, 0, 0, 0.5)")
+    );
+    assert_eq!(css("transparent").as_deref(), Some("rgba(0, 0, 0, 0)"));
+
+    for invalid in [
+        "",
+        "red",
+        "#12",
+        "#ggg",
+        "#1234567",
+        "rgb(1, 2)",
+        "rgb(1, 2, 3",
+        "rgb(a, b, c)",
+    ] {
+        assert_eq!(Color::parse(invalid), None, "{}", invalid);
+    }
+}
+
+#[test]
+fn channels_are_clamped() {
+    assert_eq!(Color::parse("rgb(300, -5, 0)").unwrap().to_css(), "#ff0000");
+    assert_eq!(Color::parse("rgba(0, 0, 0, 2)").unwrap().alpha, 1.0);
+    assert_eq!(
+        Color::new(2.0, -1.0, 0.5, 3.0),
+        Color::new(1.0, 0.0, 0.5, 1.0)
+    );
+}
+
+#[test]
+fn lighten_and_darken_follow_sass() {
+    for color in ["#3584e4", "#e01b24", "#241f31", "#808080"] {
+        let parsed = Color::parse(color).unwrap();
+        for amount in [5.0, 10.0, 30.0] {
+            assert_close(
+                parsed.lighten(amount),
+                sass(&format!("lighten({}, {}%)", color, amount)),
+            );
+            assert_close(
+                parsed.darken(amount),
+                sass(&format!("darken({}, {}%)", color, amount)),
+            );
+        }
+    }
+
+    // lightness stops at white and black
+    assert_eq!(
+        Color::parse("#f0f0f0").unwrap().lighten(50.0).to_css(),
+        "#ffffff"
+    );
+    assert_eq!(
+        Color::parse("#101010").unwrap().darken(50.0).to_css(),
+        "#000000"
+    );
+}
+
+#[test]
+fn mix_follows_sass() {
+    let (blue, white) = (
+        Color::parse("#3584e4").unwrap(),
+        Color::parse("#ffffff").unwrap(),
+    );
+    for weight in [0.0, 25.0, 50.0, 100.0] {
+        assert_close(
+            blue.mix(&white, weight),
+            sass(&format!("mix(#3584e4, #ffffff, {}%)", weight)),
+        );
+    }
+    let translucent = Color::parse("rgba(0, 0, 0, 0.5)").unwrap();
+    assert_close(
+        translucent.mix(&white, 25.0),
+        sass("mix(rgba(0, 0, 0, 0.5), #ffffff, 25%)"),
+    );
+
+    // weights outside of 0-100% are clamped
+    assert_eq!(blue.mix(&white, 150.0), blue);
+    assert_eq!(blue.mix(&white, -10.0), white);
+}
+
+#[test]
+fn contrast_fg_keeps_white_on_accents() {
+    let fg = |color: &str| Color::parse(color).unwrap().contrast_fg();
+    assert_eq!(fg("#3584e4"), "#ffffff");
+    assert_eq!(fg("#000000"), "#ffffff");
+    assert_eq!(fg("#ffffff"), "rgba(0, 0, 0, 0.8)");
+    assert_eq!(fg("#f6d32d"), "rgba(0, 0, 0, 0.8)");
+}
+
+#[test]
+fn helpers_render_colors() {
+    let data = serde_json::json!({
+        "accent": "#3584e4",
+        "reference": "@accent",
+        "mode": "light",
+    });
+    let render = |template: &str| template_registry().render_template(template, &data);
+
+    assert_eq!(render("{{darken accent 100}}").unwrap(), "#000000");
+    assert_eq!(render("{{mix accent \"#ffffff\" 0}}").unwrap(), "#ffffff");
+    assert_eq!(render("{{mix accent \"#000000\"}}").unwrap(), "#1b4272");
+    assert_eq!(
+        render("{{alpha reference 0.5}}").unwrap(),
+        "rgba(53, 132, 228, 0.5)"
+    );
+    assert_eq!(
+        render("{{to_rgba accent}}").unwrap(),
+        "rgba(53, 132, 228, 1)"
+    );
+    assert_eq!(render("{{contrast_fg reference}}").unwrap(), "#ffffff");
+    assert_eq!(
+        render("{{#if_light}}light{{else}}dark{{/if_light}}").unwrap(),
+        "light"
+    );
+    assert_eq!(
+        render("{{#if_light accent}}light{{else}}dark{{/if_light}}").unwrap(),
+        "dark"
+    );
+
+    assert!(render("{{lighten \"not a color\" 10}}").is_err());
+    assert!(render("{{lighten accent}}").is_err());
+    assert!(render("{{lighten missing 10}}").is_err());
+}
@@ -0,0 +1,19 @@
+//! Helpers shared by the integration tests, each test crate uses a part of them
+#![allow(dead_code)]
+
+use tempfile::TempDir;
+
+pub const FIXTURE: &str = include_str!("../fixtures/preset.json");
+
+/// Creates a temporary directory, removed when the returned value is dropped
+pub fn temp_dir() -> TempDir {
+    tempfile::Builder::new()
+        .prefix("gradience-test-")
+        .tempdir()
+        .unwrap()
+}
+
+/// Returns the path of a file or directory in a temporary directory
+pub fn path(dir: &TempDir, name: &str) -> String {
+    dir.path().join(name).to_str().unwrap().to_string()
+}
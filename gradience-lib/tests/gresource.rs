@@ -0,0 +1,117 @@
+mod common;
+
+use gradience_lib::gdm::Gdm;
+use gradience_lib::gresource::GResourceBuilder;
+
+fn bundle() -> Vec<u8> {
+    GResourceBuilder::new()
+        .file(
+            "/org/gnome/shell/theme/gnome-shell.css",
+            b"stage {}".to_vec(),
+        )
+        .file("/org/gnome/shell/theme/toggle-on.svg", b"<svg/>".to_vec())
+        .file(
+            "/org/gnome/shell/theme/gnome-shell-dark.css",
+            b"stage {}".to_vec(),
+        )
+        .build()
+}
+
+#[test]
+fn corrupt_bundles_are_errors() {
+    let bytes = bundle();
+    assert!(GResourceBuilder::from_bytes(b"GVariant").is_err());
+    assert!(GResourceBuilder::from_bytes(&bytes[..bytes.len() / 2]).is_err());
+
+    // every truncation and corrupted byte must be reported, not panic
+    for len in 0..bytes.len() {
+        let _ = GResourceBuilder::from_bytes(&bytes[..len]);
+    }
+    for i in 0..bytes.len() {
+        for value in [0x00, 0x7f, 0xff] {
+            let mut corrupt = bytes.clone();
+            corrupt[i] = value;
+            let _ = GResourceBuilder::from_bytes(&corrupt);
+        }
+    }
+}
+
+#[test]
+fn gdm_restore_removes_a_resource_installed_where_there_was_none() {
+    let dir = common::temp_dir();
+    let staged = common::path(&dir, "staged.gresource");
+    std::fs::write(&staged, bundle()).unwrap();
+
+    let gdm = Gdm::new().resource_path(&common::path(&dir, "gnome-shell-theme.gresource"));
+    gdm.install(&staged).unwrap();
+    assert!(gdm.is_installed());
+
+    gdm.restore().unwrap();
+    assert!(!gdm.is_installed());
+    assert!(std::fs::read_dir(dir.path())
+        .unwrap()
+        .all(|entry| entry.unwrap().file_name() == "staged.gresource"));
+}
+
+#[test]
+fn gdm_restore_puts_the_original_back() {
+    let dir = common::temp_dir();
+    let resource = common::path(&dir, "gnome-shell-theme.gresource");
+    let staged = common::path(&dir, "staged.gresource");
+    std::fs::write(&resource, "original").unwrap();
+    std::fs::write(&staged, bundle()).unwrap();
+
+    let gdm = Gdm::new().resource_path(&resource);
+    gdm.install(&staged).unwrap();
+    gdm.install(&staged).unwrap();
+    assert_eq!(std::fs::read(&resource).unwrap(), bundle());
+
+    gdm.restore().unwrap();
+    assert_eq!(std::fs::read_to_string(&resource).unwrap(), "original");
+    assert!(gdm.restore().is_err());
+}
+
+#[test]
+fn bundles_are_read_back() {
+    let bytes = bundle();
+    let read = GResourceBuilder::from_bytes(&bytes).unwrap();
+
+    assert_eq!(
+        read.paths(),
+        vec![
+            "/org/gnome/shell/theme/gnome-shell-dark.css",
+            "/org/gnome/shell/theme/gnome-shell.css",
+            "/org/gnome/shell/theme/toggle-on.svg",
+        ]
+    );
+    assert_eq!(read.build(), bytes);
+}
+
+/// Checks the bundle with the GLib tool, when it is installed
+#[test]
+fn bundles_are_read_by_glib() {
+    let dir = common::temp_dir();
+    let path = common::path(&dir, "theme.gresource");
+    std::fs::write(&path, bundle()).unwrap();
+
+    let gresource = |args: &[&str]| {
+        std::process::Command::new("gresource")
+            .args(args)
+            .output()
+            .ok()
+            .map(|output| String::from_utf8(output.stdout).unwrap())
+    };
+    let Some(list) = gresource(&["list", &path]) else {
+        return;
+    };
+    assert_eq!(
+        list,
+        "/org/gnome/shell/theme/gnome-shell-dark.css\n\
+         /org/gnome/shell/theme/gnome-shell.css\n\
+         /org/gnome/shell/theme/toggle-on.svg\n"
+    );
+    assert_eq!(
+        gresource(&["extract", &path, "/org/gnome/shell/theme/toggle-on.svg"]).unwrap(),
+        "<svg/>"
+    );
+}
@@ -0,0 +1,65 @@
+mod common;
+
+use common::FIXTURE;
+use gradience_lib::preset::Preset;
+
+fn with_license(license: Option<&str>) -> Preset {
+    let license = match license {
+        Some(license) => format!("\"license\": \"{}\",", license),
+        None => String::new(),
+    };
+    Preset::from_str(&FIXTURE.replace("\"license\": \"MIT\",", &license))
+}
+
+#[test]
+fn spdx_expressions_are_kept() {
+    for license in ["MIT OR Apache-2.0", "CC-BY-4.0", "LicenseRef-Custom"] {
+        let preset = with_license(Some(license));
+        assert_eq!(preset.license.as_str(), license);
+        assert!(preset
+            .to_str()
+            .contains(&format!("\"license\": \"{}\"", license)));
+    }
+}
+
+#[test]
+fn invalid_expressions_are_rejected() {
+    for license in ["MIT OR", "Proprietary", "GPL 2"] {
+        let preset = FIXTURE.replacen(
+            '{',
+            &format!("{{\"schema\": 3, \"license\": \"{}\",", license),
+            1,
+        );
+        let preset = preset.replace("\"license\": \"MIT\",", "");
+        assert!(Preset::from_str_migrated(&preset).is_err(), "{}", license);
+    }
+}
+
+#[test]
+fn old_license_names_are_read_as_spdx() {
+    assert_eq!(
+        with_license(Some("CCBYNCSA")).license.as_str(),
+        "CC-BY-NC-SA-4.0"
+    );
+    assert_eq!(with_license(Some("GPL3")).license.as_str(), "GPL-3.0-only");
+    assert_eq!(with_license(None).license.as_str(), "GPL-3.0-only");
+}
+
+#[test]
+fn old_license_names_are_aliases_in_current_presets() {
+    for (license, id) in [
+        ("GPL3", "GPL-3.0-only"),
+        ("GPL 3", "GPL-3.0-only"),
+        ("CCBY", "CC-BY-4.0"),
+    ] {
+        let preset = FIXTURE.replacen(
+            '{',
+            &format!("{{\"schema\": 3, \"license\": \"{}\",", license),
+            1,
+        );
+        let preset = preset.replace("\"license\": \"MIT\",", "");
+        let (preset, report) = Preset::from_str_migrated(&preset).unwrap();
+        assert_eq!(preset.license.as_str(), id);
+        assert!(report.is_empty());
+    }
+}
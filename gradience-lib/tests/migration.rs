@@ -0,0 +1,51 @@
+mod common;
+
+use common::FIXTURE;
+use gradience_lib::migration::PRESET_SCHEMA;
+use gradience_lib::preset::Preset;
+
+#[test]
+fn legacy_presets_are_migrated() {
+    let legacy = FIXTURE
+        .replace("\"palette\": {\n    \"blue\":", "\"palette\": {\n    \"blue_\":")
+        .replace("\"custom\":", "\"custom_css\":");
+    let (preset, report) = Preset::from_str_migrated(&legacy).unwrap();
+
+    assert_eq!((report.from, report.to), (1, PRESET_SCHEMA));
+    assert_eq!(
+        report.changes,
+        vec!["renamed custom_css to custom", "renamed palette.blue_ to palette.blue"]
+    );
+    assert_eq!(preset.schema, PRESET_SCHEMA);
+    assert_eq!(preset.to_str(), Preset::from_str(FIXTURE).to_str());
+}
+
+#[test]
+fn license_names_are_migrated_to_spdx() {
+    let v2 = FIXTURE
+        .replacen('{', "{\"schema\": 2,", 1)
+        .replace("\"license\": \"MIT\",", "\"license\": \"CCBYSA\",");
+    let (preset, report) = Preset::from_str_migrated(&v2).unwrap();
+
+    assert_eq!((report.from, report.to), (2, PRESET_SCHEMA));
+    assert_eq!(report.changes, vec!["changed license CCBYSA to CC-BY-SA-4.0"]);
+    assert_eq!(preset.license.as_str(), "CC-BY-SA-4.0");
+}
+
+#[test]
+fn current_presets_are_unchanged() {
+    let current = Preset::from_str(FIXTURE).to_str();
+    let (_, report) = Preset::from_str_migrated(&current).unwrap();
+    assert!(report.is_empty());
+}
+
+#[test]
+fn newer_presets_are_rejected() {
+    let newer = FIXTURE.replacen('{', &format!("{{\"schema\": {},", PRESET_SCHEMA + 1), 1);
+    assert!(Preset::from_str_migrated(&newer).is_err());
+
+    // 2^32 + 3 would be read as 3 if it was truncated
+    let overflowing = FIXTURE.replacen('{', "{\"schema\": 4294967299,", 1);
+    let err = Preset::from_str_migrated(&overflowing).unwrap_err();
+    assert_eq!(err.to_string(), "Unsupported preset schema 4294967299");
+}
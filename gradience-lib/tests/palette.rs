@@ -0,0 +1,62 @@
+use gradience_lib::preset::Palette;
+
+/// A palette with one color per hue, named after it, listed in reverse order
+fn palette() -> Palette {
+    let hues: serde_json::Map<String, serde_json::Value> = Palette::HUES
+        .iter()
+        .rev()
+        .map(|hue| {
+            (
+                hue.to_string(),
+                serde_json::json!({ "1": format!("#{}", hue) }),
+            )
+        })
+        .collect();
+    serde_json::from_value(serde_json::Value::Object(hues)).unwrap()
+}
+
+#[test]
+fn every_hue_is_iterated_in_order() {
+    let hues: Vec<&str> = palette().iter().map(|(hue, _, _)| hue).collect();
+
+    assert_eq!(hues, Palette::HUES);
+    for hue in ["teal", "pink", "slate"] {
+        assert!(hues.contains(&hue));
+    }
+}
+
+#[test]
+fn shades_are_iterated_numerically() {
+    let mut palette = palette();
+    for shade in ["10", "2", "accent"] {
+        palette
+            .teal
+            .insert(shade.to_string(), format!("#teal-{}", shade));
+    }
+
+    let shades: Vec<(&str, &str)> = palette
+        .iter()
+        .filter(|(hue, _, _)| *hue == "teal")
+        .map(|(_, shade, color)| (shade, color))
+        .collect();
+    assert_eq!(
+        shades,
+        [
+            ("1", "#teal"),
+            ("2", "#teal-2"),
+            ("10", "#teal-10"),
+            ("accent", "#teal-accent"),
+        ]
+    );
+}
+
+#[test]
+fn colors_are_looked_up_by_hue_and_shade() {
+    let palette = palette();
+
+    assert_eq!(palette.get("pink", "1"), Some("#pink"));
+    assert_eq!(palette.get("slate", "1"), Some("#slate"));
+    assert_eq!(palette.get("pink", "2"), None);
+    assert_eq!(palette.get("magenta", "1"), None);
+    assert!(palette.hue("magenta").is_none());
+}
@@ -0,0 +1,136 @@
+mod common;
+
+use common::FIXTURE;
+use gradience_lib::remote::{self, Remote};
+use tempfile::TempDir;
+
+/// Creates a directory with presets named after their file
+fn mirror(dir: &TempDir, files: &[&str]) -> String {
+    let path = common::path(dir, "mirror");
+    for file in files {
+        let file = format!("{}/{}.json", path, file);
+        std::fs::create_dir_all(std::path::Path::new(&file).parent().unwrap()).unwrap();
+        std::fs::write(
+            &file,
+            FIXTURE.replace("\"Snapshot\"", &format!("\"{}\"", file)),
+        )
+        .unwrap();
+    }
+    path
+}
+
+/// Serves the files of a directory over HTTP on a random port, a minimal stand-in for a
+/// preset repository
+#[cfg(feature = "online")]
+fn serve(dir: String) -> String {
+    use std::io::{BufRead, BufReader, Write};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut request = String::new();
+            BufReader::new(&stream).read_line(&mut request).unwrap();
+            let path = request.split(' ').nth(1).unwrap_or("/").to_string();
+            let response = match std::fs::read_to_string(format!("{}{}", dir, path)) {
+                Ok(body) => format!(
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}",
+                    body.len(),
+                    body
+                ),
+                Err(_) => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n".to_string(),
+            };
+            stream.write_all(response.as_bytes()).unwrap();
+        }
+    });
+    format!("http://{}", address)
+}
+
+#[test]
+fn remote_urls_are_checked() {
+    assert!(Remote::new("company", "https://github.com/company/presets/tree/stable").is_ok());
+    assert!(Remote::new("company", "https://github.com/company").is_err());
+    assert!(Remote::new("company", "ftp://example.com/presets").is_err());
+    assert!(Remote::new("company:presets", "file:///srv/presets").is_err());
+}
+
+#[test]
+fn directory_remotes_are_listed_and_downloaded() {
+    let temp = common::temp_dir();
+    let dir = mirror(&temp, &["nord", "dark/rose-pine"]);
+    let remote = Remote::new("mirror", &format!("file://{}", dir)).unwrap();
+
+    let names: Vec<String> = remote.list().unwrap().into_iter().map(|p| p.name).collect();
+    assert_eq!(names, vec!["dark/rose-pine", "nord"]);
+    assert!(remote
+        .download("dark/rose-pine")
+        .unwrap()
+        .name
+        .ends_with("dark/rose-pine.json"));
+}
+
+#[test]
+fn only_presets_inside_the_remote_are_used() {
+    let temp = common::temp_dir();
+    let dir = mirror(&temp, &["nord", "package", ".github/renovate", "dark/index"]);
+    std::fs::create_dir_all(format!("{}/themes.json", dir)).unwrap();
+    mirror(&temp, &["../secret"]);
+    let remote = Remote::new("mirror", &format!("file://{}", dir)).unwrap();
+
+    let names: Vec<String> = remote.list().unwrap().into_iter().map(|p| p.name).collect();
+    assert_eq!(names, vec!["nord"]);
+    for name in ["../secret", "/etc/secret", "dark/./nord", ""] {
+        assert_eq!(
+            remote.download(name).unwrap_err().kind(),
+            std::io::ErrorKind::InvalidInput,
+            "{}",
+            name
+        );
+    }
+}
+
+#[test]
+fn downloads_follow_priorities() {
+    let (low_dir, high_dir) = (common::temp_dir(), common::temp_dir());
+    let low = mirror(&low_dir, &["nord", "adwaita"]);
+    let high = mirror(&high_dir, &["nord"]);
+    let remotes = vec![
+        Remote::new("low", &format!("file://{}", low)).unwrap(),
+        Remote::new("high", &format!("file://{}", high))
+            .unwrap()
+            .priority(10),
+    ];
+
+    let name = |name: &str| remote::download(&remotes, name).map(|preset| preset.name);
+    assert!(name("nord").unwrap().starts_with(&high));
+    assert!(name("low:nord").unwrap().starts_with(&low));
+    assert!(name("adwaita").unwrap().starts_with(&low));
+    assert_eq!(
+        name("dracula").unwrap_err().kind(),
+        std::io::ErrorKind::NotFound
+    );
+}
+
+#[cfg(feature = "online")]
+#[test]
+fn index_remotes_are_listed_and_downloaded() {
+    let temp = common::temp_dir();
+    let dir = mirror(&temp, &["nord", "dark/rose-pine"]);
+    std::fs::write(
+        format!("{}/index.json", dir),
+        r#"["nord.json", "dark/rose-pine.json", "../secret.json", "/etc/secret.json"]"#,
+    )
+    .unwrap();
+    let remote = Remote::new("company", &format!("{}/index.json", serve(dir))).unwrap();
+
+    let presets = remote.list().unwrap();
+    assert_eq!(presets.len(), 2);
+    assert_eq!(presets[1].name, "dark/rose-pine");
+    assert!(presets[1].url.ends_with("/dark/rose-pine.json"));
+    assert!(remote.download("nord").unwrap().name.ends_with("nord.json"));
+    assert_eq!(
+        remote.download("dracula").unwrap_err().kind(),
+        std::io::ErrorKind::NotFound
+    );
+}
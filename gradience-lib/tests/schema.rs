@@ -0,0 +1,30 @@
+#![cfg(feature = "schema")]
+
+use gradience_lib::preset::Preset;
+use serde_json::Value;
+
+#[test]
+fn schema_is_up_to_date() {
+    let path = format!(
+        "{}/tests/snapshots/preset.schema.json",
+        env!("CARGO_MANIFEST_DIR")
+    );
+    let schema = Preset::json_schema() + "\n";
+    if std::env::var("UPDATE_SNAPSHOTS").is_ok() {
+        std::fs::write(&path, &schema).unwrap();
+        return;
+    }
+    assert!(
+        std::fs::read_to_string(&path).unwrap() == schema,
+        "the preset schema changed, run with UPDATE_SNAPSHOTS=1 to accept it"
+    );
+}
+
+#[test]
+fn schema_covers_every_variable_shape() {
+    let schema: Value = serde_json::from_str(&Preset::json_schema()).unwrap();
+    let shapes = schema["definitions"]["Variable"]["anyOf"].as_array().unwrap();
+    assert_eq!(shapes.len(), 4);
+    assert_eq!(shapes[0]["type"], "string");
+    assert_eq!(schema["definitions"]["License"]["type"], "string");
+}
@@ -0,0 +1,360 @@
+mod common;
+
+use common::FIXTURE;
+use gradience_lib::preset::{AccentsColor, Mode, Preset};
+use gradience_lib::shell::{Shell, ShellTemplates};
+use gradience_lib::utils::ShellVersion;
+use std::collections::BTreeMap;
+
+#[test]
+fn bundled_svgs_are_recolored_with_the_accent() {
+    let source = format!("{}/../shell/46", env!("CARGO_MANIFEST_DIR"));
+    let dir = common::temp_dir();
+    let target = common::path(&dir, "build");
+    let preset = Preset::from_str(FIXTURE);
+    // the only bundled asset using the Adwaita accent colors
+    let upstream = std::fs::read_to_string(format!("{}/gnome-shell-start.svg", source)).unwrap();
+    assert!(upstream.contains("#3580e4") && upstream.contains("#8abfdd"));
+
+    ShellTemplates::load(&source)
+        .unwrap()
+        .render(&preset, Mode::Dark, AccentsColor::Red, &target)
+        .unwrap();
+
+    let data = preset.template_data(Mode::Dark, AccentsColor::Red);
+    let svg = std::fs::read_to_string(format!("{}/gnome-shell-start.svg", target)).unwrap();
+    assert!(svg.contains(&data["accent_bg_color"]));
+    assert!(svg.contains(&data["accent_color"]));
+    assert!(!svg.to_lowercase().contains("#3580e4"));
+    assert!(!svg.to_lowercase().contains("#8abfdd"));
+}
+
+#[test]
+fn template_values_are_not_html_escaped() {
+    let mut preset = Preset::from_str(FIXTURE);
+    preset.custom.shell =
+        "#panel > .button:hover { font-family: \"Cantarell\" & \"Inter\"; }".to_string();
+
+    assert_eq!(
+        preset.render_template("{{custom_css}}".to_string(), Mode::Dark, AccentsColor::Blue),
+        preset.custom.shell
+    );
+}
+
+/// Writes a shell source made of a single colors template
+fn template_source(dir: &tempfile::TempDir, template: &str) -> String {
+    let source = common::path(dir, "source");
+    std::fs::create_dir_all(format!("{}/gnome-shell-sass", source)).unwrap();
+    std::fs::write(
+        format!("{}/gnome-shell-sass/_colors.template", source),
+        template,
+    )
+    .unwrap();
+    source
+}
+
+#[test]
+fn bundled_templates_only_use_preset_variables() {
+    let source = format!("{}/../shell/46", env!("CARGO_MANIFEST_DIR"));
+    let preset = Preset::from_str(FIXTURE);
+
+    let missing = ShellTemplates::load(&source)
+        .unwrap()
+        .check(&preset)
+        .unwrap();
+    assert!(missing.is_empty(), "{:?}", missing);
+}
+
+#[test]
+fn unknown_variables_are_reported_in_every_variant() {
+    let dir = common::temp_dir();
+    let source = template_source(
+        &dir,
+        "$bg: {{window_bg_color}};\n{{#if_light}}$fg: {{window_fg_color}};{{else}}$fg: {{night_color}};{{/if_light}}\n",
+    );
+    let preset = Preset::from_str(FIXTURE);
+
+    let missing = ShellTemplates::load(&source)
+        .unwrap()
+        .check(&preset)
+        .unwrap();
+    assert_eq!(missing.len(), 1);
+    assert_eq!(missing[0].name, "night_color");
+    assert_eq!(missing[0].line, 2);
+    assert_eq!(
+        missing[0].template,
+        format!("{}/gnome-shell-sass/_colors.template", source)
+    );
+}
+
+#[test]
+fn strict_rendering_fails_on_unknown_variables() {
+    let dir = common::temp_dir();
+    let source = template_source(&dir, "$fg: {{night_color}};\n");
+    let target = common::path(&dir, "build");
+    let preset = Preset::from_str(FIXTURE);
+
+    let templates = ShellTemplates::load(&source).unwrap();
+    templates
+        .render(&preset, Mode::Dark, AccentsColor::Blue, &target)
+        .unwrap();
+    assert_eq!(
+        std::fs::read_to_string(format!("{}/gnome-shell-sass/_colors.scss", target)).unwrap(),
+        "$fg: ;\n"
+    );
+
+    let err = templates
+        .strict(true)
+        .render(&preset, Mode::Dark, AccentsColor::Blue, &target)
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("missing variable `night_color`"),
+        "{}",
+        err
+    );
+}
+
+/// Writes a shell source with a colors partial and a panel widget partial
+fn scss_source(dir: &tempfile::TempDir) -> String {
+    let source = common::path(dir, "source");
+    std::fs::create_dir_all(format!("{}/gnome-shell-sass/widgets", source)).unwrap();
+    std::fs::write(
+        format!("{}/gnome-shell-sass/_colors.scss", source),
+        "$panel_height: 32px;\n$panel_padding: $panel_height / 8;\n",
+    )
+    .unwrap();
+    std::fs::write(
+        format!("{}/gnome-shell-sass/widgets/_panel.scss", source),
+        "#panel { height: $panel_height; }\n",
+    )
+    .unwrap();
+    source
+}
+
+#[test]
+fn shell_variables_replace_the_defaults() {
+    let dir = common::temp_dir();
+    let source = scss_source(&dir);
+    let target = common::path(&dir, "build");
+    let mut preset = Preset::from_str(FIXTURE);
+    preset
+        .custom
+        .shell_variables
+        .insert("panel_height".to_string(), "40px".to_string());
+
+    ShellTemplates::load(&source)
+        .unwrap()
+        .render(&preset, Mode::Dark, AccentsColor::Blue, &target)
+        .unwrap();
+
+    assert_eq!(
+        std::fs::read_to_string(format!("{}/gnome-shell-sass/_colors.scss", target)).unwrap(),
+        "$panel_height: 40px;\n$panel_padding: $panel_height / 8;\n"
+    );
+}
+
+#[test]
+fn shell_widgets_are_appended_to_their_partial() {
+    let dir = common::temp_dir();
+    let source = scss_source(&dir);
+    let target = common::path(&dir, "build");
+    let mut preset = Preset::from_str(FIXTURE);
+    preset.custom.shell_widgets.insert(
+        "panel".to_string(),
+        "#panel { border-radius: 8px; }".to_string(),
+    );
+
+    ShellTemplates::load(&source)
+        .unwrap()
+        .render(&preset, Mode::Dark, AccentsColor::Blue, &target)
+        .unwrap();
+
+    assert_eq!(
+        std::fs::read_to_string(format!("{}/gnome-shell-sass/widgets/_panel.scss", target))
+            .unwrap(),
+        "#panel { height: $panel_height; }\n\n/* Preset: panel */\n#panel { border-radius: 8px; }\n"
+    );
+}
+
+#[test]
+fn unknown_shell_customizations_are_rejected() {
+    let dir = common::temp_dir();
+    let source = scss_source(&dir);
+    let target = common::path(&dir, "build");
+    let templates = ShellTemplates::load(&source).unwrap();
+
+    let mut preset = Preset::from_str(FIXTURE);
+    preset
+        .custom
+        .shell_widgets
+        .insert("dock".to_string(), "#dock {}".to_string());
+    let err = templates
+        .render(&preset, Mode::Dark, AccentsColor::Blue, &target)
+        .unwrap_err();
+    assert_eq!(err.to_string(), "Unknown shell widget dock");
+
+    let mut preset = Preset::from_str(FIXTURE);
+    preset
+        .custom
+        .shell_variables
+        .insert("dock_size".to_string(), "48px".to_string());
+    let err = templates
+        .render(&preset, Mode::Dark, AccentsColor::Blue, &target)
+        .unwrap_err();
+    assert_eq!(err.to_string(), "Unknown shell variable $dock_size");
+}
+
+#[test]
+fn later_layers_take_precedence() {
+    let dir = common::temp_dir();
+    let layers: Vec<String> = ["base", "first", "second"]
+        .iter()
+        .map(|layer| common::path(&dir, layer))
+        .collect();
+    let write = |layer: &str, name: &str, content: &str| {
+        let path = std::path::Path::new(layer).join(name);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    };
+    write(
+        &layers[0],
+        "gnome-shell-sass/_colors.template",
+        "$c: base;\n",
+    );
+    write(&layers[0], "gnome-shell-sass/_common.scss", "$c: base;\n");
+    write(&layers[0], "gnome-shell-sass/_drawing.scss", "$c: base;\n");
+    write(&layers[1], "gnome-shell-sass/_colors.scss", "$c: first;\n");
+    write(&layers[1], "gnome-shell-sass/_common.scss", "$c: first;\n");
+    write(&layers[2], "gnome-shell-sass/_common.scss", "$c: second;\n");
+    write(&layers[2], "gnome-shell-sass/_drawing.scss", "$c: plain;\n");
+    write(
+        &layers[2],
+        "gnome-shell-sass/_drawing.template",
+        "$c: {{mode}};\n",
+    );
+
+    let templates = ShellTemplates::load_layers(&layers).unwrap();
+    assert_eq!(
+        templates.sources(),
+        vec![
+            (
+                "gnome-shell-sass/_colors.scss".to_string(),
+                layers[1].clone()
+            ),
+            (
+                "gnome-shell-sass/_common.scss".to_string(),
+                layers[2].clone()
+            ),
+            (
+                "gnome-shell-sass/_drawing.template".to_string(),
+                layers[2].clone()
+            ),
+        ]
+    );
+
+    let target = common::path(&dir, "build");
+    templates
+        .render(
+            &Preset::from_str(FIXTURE),
+            Mode::Dark,
+            AccentsColor::Blue,
+            &target,
+        )
+        .unwrap();
+    let read = |name: &str| {
+        std::fs::read_to_string(format!("{}/gnome-shell-sass/{}", target, name)).unwrap()
+    };
+    assert_eq!(read("_colors.scss"), "$c: first;\n");
+    assert_eq!(read("_common.scss"), "$c: second;\n");
+    assert_eq!(read("_drawing.scss"), "$c: dark;\n");
+}
+
+/// Files of a built theme
+const THEME_FILES: [&str; 4] = [
+    "gnome-shell/gnome-shell.css",
+    "gtk-3.0/gtk.css",
+    "gtk-4.0/gtk.css",
+    "index.theme",
+];
+
+fn bundled_shell() -> Shell {
+    Shell::with_version(
+        format!("{}/../shell", env!("CARGO_MANIFEST_DIR")),
+        Preset::from_str(FIXTURE),
+        ShellVersion::G46,
+    )
+}
+
+/// Returns the content of every file in a directory, by relative path
+fn read_tree(dir: &str) -> BTreeMap<String, Vec<u8>> {
+    walkdir::WalkDir::new(dir)
+        .into_iter()
+        .map(|entry| entry.unwrap())
+        .filter(|entry| entry.file_type().is_file())
+        .map(|entry| {
+            let relative = entry.path().strip_prefix(dir).unwrap();
+            (
+                relative.to_str().unwrap().to_string(),
+                std::fs::read(entry.path()).unwrap(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn every_variant_is_built() {
+    let dir = common::temp_dir();
+    let themes_dir = common::path(&dir, "themes");
+    let shell = bundled_shell();
+
+    let themes = shell
+        .build_all_variants(&common::path(&dir, "build"), &themes_dir)
+        .unwrap();
+
+    assert_eq!(themes.len(), Mode::ALL.len() * AccentsColor::ALL.len());
+    for mode in Mode::ALL {
+        for accent in AccentsColor::ALL {
+            let theme = format!("{}/{}", themes_dir, shell.theme_name(mode, accent));
+            assert!(themes.contains(&theme), "{} wasn't built", theme);
+            for file in THEME_FILES {
+                let content = std::fs::read(format!("{}/{}", theme, file)).unwrap();
+                assert!(!content.is_empty(), "{}/{} is empty", theme, file);
+            }
+        }
+    }
+}
+
+// grass may order the selectors generated by `@extend` differently from one compilation
+// to another, so the shell stylesheets are compared through their rendered sources
+#[cfg(feature = "parallel")]
+#[test]
+fn parallel_builds_match_serial_builds() {
+    let dir = common::temp_dir();
+    let shell = bundled_shell();
+
+    let serial = shell
+        .build_all_variants(&common::path(&dir, "build"), &common::path(&dir, "serial"))
+        .unwrap();
+    let parallel = shell
+        .build_all_variants_parallel(
+            &common::path(&dir, "parallel-build"),
+            &common::path(&dir, "parallel"),
+        )
+        .unwrap();
+
+    assert_eq!(
+        read_tree(&common::path(&dir, "build")),
+        read_tree(&common::path(&dir, "parallel-build"))
+    );
+    assert_eq!(serial.len(), parallel.len());
+    for (serial, parallel) in serial.iter().zip(&parallel) {
+        for file in &THEME_FILES[1..] {
+            assert_eq!(
+                std::fs::read(format!("{}/{}", serial, file)).unwrap(),
+                std::fs::read(format!("{}/{}", parallel, file)).unwrap(),
+                "{} differs",
+                file
+            );
+        }
+    }
+}
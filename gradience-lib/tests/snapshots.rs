@@ -0,0 +1,101 @@
+//! Generated output must be stable so it can be diffed and committed. Snapshots are
+//! stored in `tests/snapshots`, run with `UPDATE_SNAPSHOTS=1` to write them again.
+
+mod common;
+
+use common::FIXTURE;
+use gradience_lib::preset::{AccentsColor, GtkVersion, Mode, Preset};
+
+fn assert_snapshot(name: &str, actual: &str) {
+    let path = format!("{}/tests/snapshots/{}", env!("CARGO_MANIFEST_DIR"), name);
+    if std::env::var("UPDATE_SNAPSHOTS").is_ok() {
+        std::fs::write(&path, actual).unwrap();
+        return;
+    }
+
+    let expected = std::fs::read_to_string(&path)
+        .unwrap_or_else(|_| panic!("missing snapshot {}, run with UPDATE_SNAPSHOTS=1", path));
+    assert!(
+        expected == actual,
+        "{} changed, run with UPDATE_SNAPSHOTS=1 to accept:\n{}",
+        name,
+        actual
+    );
+}
+
+#[test]
+fn gtk4_css() {
+    let preset = Preset::from_str(FIXTURE);
+    assert_snapshot(
+        "gtk4-light-blue.css",
+        &preset.to_css(Mode::Light, AccentsColor::Blue, GtkVersion::Gtk4),
+    );
+}
+
+#[test]
+fn gtk3_css() {
+    let preset = Preset::from_str(FIXTURE);
+    assert_snapshot(
+        "gtk3-dark-red.css",
+        &preset.to_css(Mode::Dark, AccentsColor::Red, GtkVersion::Gtk3),
+    );
+}
+
+#[test]
+fn template_data() {
+    let preset = Preset::from_str(FIXTURE);
+    let rendered = preset.render_template(
+        "{{#each this}}{{@key}}: {{this}}\n{{/each}}".to_string(),
+        Mode::Dark,
+        AccentsColor::Green,
+    );
+    assert_snapshot("template-data-dark-green.txt", &rendered);
+}
+
+#[test]
+fn index_theme() {
+    let preset = Preset::from_str(FIXTURE);
+    assert_snapshot(
+        "index-dark-teal.theme",
+        &preset.to_index_theme("Snapshot-Dark-Teal", Mode::Dark, AccentsColor::Teal),
+    );
+}
+
+#[test]
+fn serialized_preset() {
+    let preset = Preset::from_str(FIXTURE);
+    assert_snapshot("preset.json", &preset.to_str());
+}
+
+#[test]
+fn serialized_preset_without_defaults() {
+    let preset = Preset::from_str(FIXTURE);
+    assert_snapshot(
+        "preset-without-defaults.json",
+        &preset.to_str_without_defaults(),
+    );
+}
+
+#[test]
+fn output_is_stable_between_loads() {
+    // maps are hashed with a different seed each time a preset is loaded
+    let first = Preset::from_str(FIXTURE);
+    for _ in 0..16 {
+        let preset = Preset::from_str(FIXTURE);
+        assert_eq!(
+            first.to_css(Mode::Light, AccentsColor::Blue, GtkVersion::Gtk4),
+            preset.to_css(Mode::Light, AccentsColor::Blue, GtkVersion::Gtk4)
+        );
+        assert_eq!(first.to_str(), preset.to_str());
+    }
+}
+
+#[test]
+fn serialization_round_trips() {
+    let preset = Preset::from_str(FIXTURE);
+    let serialized = preset.to_str();
+    assert_eq!(Preset::from_str(&serialized).to_str(), serialized);
+
+    let without_defaults = preset.to_str_without_defaults();
+    assert_eq!(Preset::from_str(&without_defaults).to_str(), serialized);
+}
@@ -0,0 +1,180 @@
+mod common;
+
+use common::FIXTURE;
+use gradience_lib::preset::Preset;
+use gradience_lib::store::{Search, Store};
+use tempfile::TempDir;
+
+/// Creates a store in a temporary directory with presets of the given names, authors and
+/// supported modes
+fn store(dir: &TempDir, presets: &[(&str, &str, &str)]) -> Store {
+    let mut store = Store::new(common::path(dir, "presets"));
+    for (name, author, mode) in presets {
+        let mut preset = Preset::from_str(FIXTURE);
+        preset.name = name.to_string();
+        preset.author.name = author.to_string();
+        preset.supported.mode = vec![mode.to_string()];
+        store.add_preset(preset);
+    }
+    store.save_presets();
+
+    let mut store = Store::new(store.base_path.clone());
+    store.load();
+    store
+}
+
+fn names(store: &Store, search: &Search) -> Vec<String> {
+    store.search(search).into_iter().map(|e| e.name).collect()
+}
+
+#[test]
+fn index_lists_presets_by_name() {
+    let dir = common::temp_dir();
+    let store = store(
+        &dir,
+        &[("Nord", "Arctic", "dark"), ("Adwaita", "GNOME", "light")],
+    );
+    let index = store.index();
+
+    assert_eq!(index[0].name, "Adwaita");
+    assert_eq!(index[0].license, "MIT");
+    assert_eq!(index[1].modes, vec!["dark"]);
+    assert!(index[1].path.ends_with("/nord.json"));
+}
+
+#[test]
+fn search_is_fuzzy_and_filtered() {
+    let dir = common::temp_dir();
+    let store = store(
+        &dir,
+        &[
+            ("Rosé Pine", "Ana", "dark"),
+            ("Pinewood", "Bob", "light"),
+            ("Nord", "Pine Studio", "dark"),
+        ],
+    );
+
+    assert_eq!(names(&store, &Search::new("rspine")), vec!["Rosé Pine"]);
+    // names count more than authors
+    assert_eq!(
+        names(&store, &Search::new("pine")),
+        vec!["Pinewood", "Rosé Pine", "Nord"]
+    );
+    assert_eq!(
+        names(&store, &Search::new("pine").mode(Some("dark".to_string()))),
+        vec!["Rosé Pine", "Nord"]
+    );
+    assert_eq!(
+        names(&store, &Search::new("").author(Some("bob".to_string()))),
+        vec!["Pinewood"]
+    );
+}
+
+#[test]
+fn load_skips_and_reports_broken_files() {
+    let dir = common::temp_dir();
+    let store = store(&dir, &[("Nord", "Arctic", "dark")]);
+    let path = store.base_path.clone();
+    std::fs::write(format!("{}/README.md", path), "# Presets").unwrap();
+    std::fs::write(format!("{}/Broken.json", path), "{").unwrap();
+    std::fs::write(format!("{}/.Nord.json.swp", path), "").unwrap();
+    std::fs::create_dir_all(format!("{}/more", path)).unwrap();
+    std::fs::write(format!("{}/more/Old.json", path), FIXTURE).unwrap();
+
+    let mut store = Store::new(path.clone());
+    let report = store.load();
+    assert_eq!(report.loaded, 1);
+    assert_eq!(report.skipped, vec![format!("{}/README.md", path)]);
+    assert_eq!(report.errors.len(), 1);
+    assert_eq!(report.errors[0].0, format!("{}/Broken.json", path));
+
+    let mut store = Store::new(path.clone()).recursive(true);
+    let report = store.load();
+    assert_eq!(report.loaded, 2);
+    assert_eq!(report.outdated, vec![format!("{}/more/Old.json", path)]);
+}
+
+#[test]
+fn presets_are_found_by_id_filename_or_name() {
+    let dir = common::temp_dir();
+    let mut store = store(
+        &dir,
+        &[("Rosé Pine", "Ana", "dark"), ("Nord", "Arctic", "dark")],
+    );
+    let find = |store: &Store, query: &str| store.get_preset(query).map(|p| p.name.clone());
+
+    assert_eq!(find(&store, "rose-pine").unwrap(), "Rosé Pine");
+    assert_eq!(find(&store, "rose-pine.json").unwrap(), "Rosé Pine");
+    assert_eq!(find(&store, "ROSÉ PINE").unwrap(), "Rosé Pine");
+    assert!(find(&store, "rose").is_err());
+
+    let mut copy = Preset::from_str(FIXTURE);
+    copy.name = "nord".to_string();
+    copy.id = Some("arctic".to_string());
+    store.add_preset(copy);
+    assert_eq!(find(&store, "nord").unwrap(), "Nord");
+    assert_eq!(find(&store, "arctic").unwrap(), "nord");
+    assert_eq!(
+        find(&store, "Nord").unwrap_err().kind(),
+        std::io::ErrorKind::InvalidInput
+    );
+}
+
+#[test]
+fn later_layers_hide_presets_and_layers_are_read_only() {
+    let system_dir = common::temp_dir();
+    let system = store(
+        &system_dir,
+        &[("Nord", "System", "dark"), ("Adwaita", "System", "light")],
+    );
+    let user_dir = common::temp_dir();
+    let user = store(&user_dir, &[("Nord", "User", "dark")]);
+
+    let mut store = Store::new(user.base_path.clone()).layers(vec![system.base_path.clone()]);
+    let report = store.load();
+    assert_eq!(
+        report.hidden,
+        vec![format!("{}/nord.json", system.base_path)]
+    );
+
+    let index = store.index();
+    assert_eq!(
+        (index[0].name.as_str(), index[0].read_only),
+        ("Adwaita", true)
+    );
+    assert_eq!(index[0].layer, system.base_path);
+    assert_eq!(
+        (index[1].author.as_str(), index[1].read_only),
+        ("User", false)
+    );
+
+    let error = store.remove_preset("adwaita").unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::PermissionDenied);
+    assert!(std::path::Path::new(&index[0].path).exists());
+}
+
+#[test]
+fn presets_saved_by_name_are_renamed_to_their_id() {
+    let dir = common::temp_dir();
+    let path = common::path(&dir, "presets");
+    std::fs::create_dir_all(&path).unwrap();
+    let mut old = Preset::from_str(FIXTURE);
+    old.name = "Rosé Pine".to_string();
+    old.to_file(&format!("{}/Rosé Pine.json", path));
+
+    let mut store = Store::new(path.clone());
+    store.load();
+    let mut preset = store.get_preset("rose-pine").unwrap().clone();
+    preset.version = "2.0".to_string();
+    store.add_preset(preset);
+    store.save_presets();
+
+    let files: Vec<String> = std::fs::read_dir(&path)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    assert_eq!(files, vec!["rose-pine.json"]);
+    let mut store = Store::new(path);
+    store.load();
+    assert_eq!(store.get_preset("rose-pine").unwrap().version, "2.0");
+}
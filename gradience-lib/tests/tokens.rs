@@ -0,0 +1,161 @@
+mod common;
+
+use common::FIXTURE;
+use gradience_lib::preset::{AccentsColor, Mode, Preset};
+use gradience_lib::shell::{Shell, ShellTemplates, ThemeName};
+use gradience_lib::tokens::{Length, LengthUnit, Tint, Tokens};
+use gradience_lib::utils::ShellVersion;
+use std::collections::BTreeMap;
+
+/// The fixture with a font size and a window radius in em
+fn em_tokens() -> Preset {
+    let mut preset = Preset::from_str(FIXTURE);
+    preset.tokens.window_radius = Length::parse("1em");
+    preset.tokens.font.as_mut().unwrap().size = Length::parse("1.5em");
+    preset
+}
+
+#[test]
+fn lengths_are_parsed_and_displayed() {
+    let length = |value, unit| Some(Length { value, unit });
+
+    assert_eq!(Length::parse("12px"), length(12.0, LengthUnit::Px));
+    assert_eq!(Length::parse(" 10.5 pt "), length(10.5, LengthUnit::Pt));
+    assert_eq!(Length::parse("1.5em"), length(1.5, LengthUnit::Em));
+    assert_eq!(Length::parse("-2"), length(-2.0, LengthUnit::Px));
+    assert_eq!(Length::parse("12rem"), None);
+    assert_eq!(Length::parse("px"), None);
+
+    assert_eq!(Length::parse("1.5em").unwrap().to_string(), "1.5em");
+    assert_eq!(Length::px(8.0).to_string(), "8px");
+}
+
+#[test]
+fn only_radius_and_font_tokens_apply_to_gtk() {
+    let tokens = Tokens {
+        spacing: Length::parse("8px"),
+        panel_opacity: Some(0.5),
+        dash_opacity: Some(0.5),
+        ..Tokens::default()
+    };
+    assert_eq!(tokens.to_css(), "");
+
+    let tokens = Tokens {
+        button_radius: Length::parse("4px"),
+        ..tokens
+    };
+    assert_eq!(tokens.to_css(), "button { border-radius: 4px; }\n");
+}
+
+#[test]
+fn em_lengths_are_given_to_shell_templates_in_points() {
+    let preset = em_tokens();
+    let data = preset.template_data(Mode::Dark, AccentsColor::Blue);
+
+    assert_eq!(data["font_size"], "16.5pt");
+    assert_eq!(data["window_radius"], "16.5pt");
+}
+
+#[test]
+fn panel_opacity_applies_to_unresolved_colors() {
+    let tokens = Tokens {
+        panel_opacity: Some(0.5),
+        ..Tokens::default()
+    };
+    let panel = |color: &str| {
+        let colors = BTreeMap::from([("panel_bg_color".to_string(), color.to_string())]);
+        tokens.template_data(&colors)["panel_background"].clone()
+    };
+
+    assert_eq!(panel("#000000"), "rgba(0, 0, 0, 0.5)");
+    assert_eq!(
+        panel("darken($bg_color, 5%)"),
+        "rgba($panel_bg_color, alpha($panel_bg_color) * 0.5)"
+    );
+}
+
+#[test]
+fn dash_opacity_multiplies_the_color_alpha() {
+    let tokens = Tokens {
+        dash_opacity: Some(0.5),
+        ..Tokens::default()
+    };
+
+    assert_eq!(
+        tokens.template_data(&BTreeMap::new())["dash_background"],
+        "rgba($system_overlay_bg_color, alpha($system_overlay_bg_color) * 0.5)"
+    );
+}
+
+#[test]
+fn unresolved_overview_tints_are_rejected() {
+    let tint = |color: &str| Tokens {
+        overview_tint: Some(Tint {
+            color: color.to_string(),
+            strength: 20.0,
+        }),
+        ..Tokens::default()
+    };
+    let colors = BTreeMap::from([("accent_bg_color".to_string(), "#3584e4".to_string())]);
+
+    assert!(tint("@accent_bg_color").check_colors(&colors).is_ok());
+    assert!(tint("#ff0000").check_colors(&colors).is_ok());
+    assert!(tint("@accent").check_colors(&colors).is_err());
+    assert!(tint("$accent_bg_color").check_colors(&colors).is_err());
+
+    let dir = common::temp_dir();
+    let source = common::path(&dir, "source");
+    std::fs::create_dir_all(&source).unwrap();
+    std::fs::write(
+        format!("{}/gnome-shell.template", source),
+        "$bg: {{overview_background}};\n",
+    )
+    .unwrap();
+    let mut preset = Preset::from_str(FIXTURE);
+    preset.tokens = tint("@accent");
+    let err = ShellTemplates::load(&source)
+        .unwrap()
+        .render(
+            &preset,
+            Mode::Dark,
+            AccentsColor::Blue,
+            &common::path(&dir, "build"),
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Unable to resolve the overview tint color @accent"
+    );
+}
+
+#[test]
+fn shell_arithmetic_accepts_em_tokens() {
+    let dir = common::temp_dir();
+    let source = common::path(&dir, "source");
+    // the expressions upstream uses with the font size and the window radius
+    std::fs::create_dir_all(format!("{}/46", source)).unwrap();
+    std::fs::write(
+        format!("{}/46/gnome-shell.template", source),
+        "$base_font_size: {{font_size}};\n\
+         $modal_radius: {{window_radius}};\n\
+         stage { font-size: $base_font_size + 5pt; border-radius: $modal_radius - 1px; }\n",
+    )
+    .unwrap();
+
+    let preset = em_tokens();
+    let shell = Shell::with_version(source, preset, ShellVersion::G46);
+    let theme = shell
+        .build(
+            &shell.load_templates().unwrap(),
+            &common::path(&dir, "build"),
+            &common::path(&dir, "themes"),
+            Mode::Dark,
+            AccentsColor::Blue,
+            &ThemeName::Default,
+        )
+        .unwrap();
+
+    let css = std::fs::read_to_string(format!("{}/gnome-shell/gnome-shell.css", theme)).unwrap();
+    assert!(css.contains("font-size: 21.5pt"), "{}", css);
+    assert!(css.contains("border-radius: 15.75pt"), "{}", css);
+}